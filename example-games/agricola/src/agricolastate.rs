@@ -3,6 +3,44 @@ use std::fmt::Display;
 use std::fmt;
 use rand::Rng;
 
+/// Part of an action that is chosen step by step, so the search plans it like any other move
+#[derive(Debug, Clone, PartialEq)]
+pub enum PendingDecision {
+    /// Choose a crop for each empty field, then bake bread if the action included it
+    Sow { bake_bread: bool },
+    /// Fence pastures one at a time
+    Fences,
+    /// Build stables one at a time
+    Stables,
+    /// House the animals that have been taken off the farm, one housing at a time
+    Animals { sheep: usize, boar: usize, cattle: usize },
+    /// Pay for this Cooking Hearth with clay or by returning a fireplace
+    CookingHearth(MajorImprovement),
+}
+
+impl PendingDecision {
+    pub fn stable_hash(&self, hasher: &mut StableHasher) {
+        match *self {
+            PendingDecision::Sow { bake_bread } => {
+                hasher.write(0);
+                hasher.write(bake_bread as u64);
+            },
+            PendingDecision::Fences => hasher.write(1),
+            PendingDecision::Stables => hasher.write(2),
+            PendingDecision::Animals { sheep, boar, cattle } => {
+                hasher.write(3);
+                for &count in &[sheep, boar, cattle] {
+                    hasher.write(count as u64);
+                }
+            },
+            PendingDecision::CookingHearth(ref improvement) => {
+                hasher.write(4);
+                hasher.write(improvement.clone() as u64);
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AgricolaState {
     pub players: Vec<Player>,
@@ -14,132 +52,184 @@ pub struct AgricolaState {
     pub total_rounds: usize,
     pub actions_taken: Vec<String>,
     pub available_improvements: Vec<MajorImprovement>,
+    /// The round is over and the next action card has to be drawn before anyone can move
+    pub pending_draw: bool,
+    /// Choices the current player still has to make to finish their last action
+    pub pending_decision: Option<PendingDecision>,
     well_player: Option<usize>,
     well_food: usize
 }
 
 impl State for AgricolaState {
-    fn get_actions(&self) -> Vec<u32> {
-        if self.rounds > self.total_rounds {
-            // Game over!
+    type Action = AgricolaAction;
+
+    fn get_actions(&self) -> Vec<AgricolaAction> {
+        if self.rounds > self.total_rounds || self.pending_draw {
+            // Game over or waiting on the next action card
             return Vec::new();
         }
 
+        if let Some(ref decision) = self.pending_decision {
+            let player = &self.players[self.current_player];
+            let mut actions = Vec::new();
+            match *decision {
+                PendingDecision::Sow { .. } => {
+                    if player.grains > 0 {
+                        actions.push(AgricolaAction::SowGrain);
+                    }
+                    if player.vegetables > 0 {
+                        actions.push(AgricolaAction::SowVegetable);
+                    }
+                    actions.push(AgricolaAction::FinishSowing);
+                },
+                PendingDecision::Fences => {
+                    for pasture in player.pasture_options() {
+                        actions.push(AgricolaAction::FencePasture(pasture));
+                    }
+                    actions.push(AgricolaAction::FinishFences);
+                },
+                PendingDecision::Stables => {
+                    for tile in player.stable_options() {
+                        actions.push(AgricolaAction::BuildStableOn(tile));
+                    }
+                    actions.push(AgricolaAction::FinishStables);
+                },
+                PendingDecision::Animals { sheep, boar, cattle } => {
+                    for housing in player.housing_options() {
+                        for &(ref animal, count) in &[(Animal::Sheep, sheep), (Animal::Boar, boar), (Animal::Cattle, cattle)] {
+                            if count > 0 {
+                                actions.push(AgricolaAction::HouseAnimals(housing.clone(), animal.clone()));
+                            }
+                        }
+                    }
+                    actions.push(AgricolaAction::FinishAnimals);
+                },
+                PendingDecision::CookingHearth(_) => {
+                    actions.push(AgricolaAction::PayClay);
+                    actions.push(AgricolaAction::ReturnFireplace);
+                }
+            }
+            return actions;
+        }
+
         let mut actions = Vec::new();
 
         for (tile, board_tile) in &(self.board.tiles) {
             if board_tile.occupied.is_none() {
                 match tile {
                     &AgricolaTile::DayLaborer => {
-                        actions.push(AgricolaAction::DayLaborer_Food_Wood as u32);
-                        actions.push(AgricolaAction::DayLaborer_Food_Clay as u32);
-                        actions.push(AgricolaAction::DayLaborer_Food_Reed as u32);
-                        actions.push(AgricolaAction::DayLaborer_Food_Stone as u32);
+                        actions.push(AgricolaAction::DayLaborer_Food_Wood);
+                        actions.push(AgricolaAction::DayLaborer_Food_Clay);
+                        actions.push(AgricolaAction::DayLaborer_Food_Reed);
+                        actions.push(AgricolaAction::DayLaborer_Food_Stone);
                     },
                     &AgricolaTile::BuildRoom_BuildStables => {
-                        actions.push(AgricolaAction::BuildRoom as u32);
-                        actions.push(AgricolaAction::BuildStables as u32);
-                        actions.push(AgricolaAction::BuildRoom_BuildStables as u32);
+                        actions.push(AgricolaAction::BuildRoom);
+                        actions.push(AgricolaAction::BuildStables);
+                        actions.push(AgricolaAction::BuildRoom_BuildStables);
                     }
-                    &AgricolaTile::StartingPlayer_Food => actions.push(AgricolaAction::StartingPlayer_Food as u32),
-                    &AgricolaTile::Grain => actions.push(AgricolaAction::Grain as u32),
-                    &AgricolaTile::Plow  => actions.push(AgricolaAction::Plow as u32),
+                    &AgricolaTile::StartingPlayer_Food => actions.push(AgricolaAction::StartingPlayer_Food),
+                    &AgricolaTile::Grain => actions.push(AgricolaAction::Grain),
+                    &AgricolaTile::Plow  => actions.push(AgricolaAction::Plow),
                     &AgricolaTile::BuildStable_BakeBread  => {
-                        actions.push(AgricolaAction::BuildStable_BakeBread as u32);
-                        actions.push(AgricolaAction::BuildStable as u32);
-                        actions.push(AgricolaAction::BakeBread_NoStable as u32);
+                        actions.push(AgricolaAction::BuildStable_BakeBread);
+                        actions.push(AgricolaAction::BuildStable);
+                        actions.push(AgricolaAction::BakeBread_NoStable);
                     }
                     &AgricolaTile::Sow_BakeBread  => {
-                        actions.push(AgricolaAction::Sow_BakeBread as u32);
-                        actions.push(AgricolaAction::BakeBread_NotSow as u32);
-                        actions.push(AgricolaAction::Sow as u32);
+                        actions.push(AgricolaAction::Sow_BakeBread);
+                        actions.push(AgricolaAction::BakeBread_NotSow);
+                        actions.push(AgricolaAction::Sow);
                     },
-                    &AgricolaTile::Wood  => actions.push(AgricolaAction::Wood as u32),
-                    &AgricolaTile::Clay  => actions.push(AgricolaAction::Clay as u32),
-                    &AgricolaTile::Reed  => actions.push(AgricolaAction::Reed as u32),
-                    &AgricolaTile::Fishing  => actions.push(AgricolaAction::Fishing as u32),
+                    &AgricolaTile::Wood  => actions.push(AgricolaAction::Wood),
+                    &AgricolaTile::Clay  => actions.push(AgricolaAction::Clay),
+                    &AgricolaTile::Reed  => actions.push(AgricolaAction::Reed),
+                    &AgricolaTile::Fishing  => actions.push(AgricolaAction::Fishing),
                     &AgricolaTile::Fences  => {
-                        actions.push(AgricolaAction::Fences as u32);
+                        actions.push(AgricolaAction::Fences);
                     },
                     &AgricolaTile::MajorImprovement  => {
                         if self.available_improvements.contains(&MajorImprovement::Fireplace_2) {
-                            actions.push(AgricolaAction::MajorImprovement_Fireplace_2 as u32);
+                            actions.push(AgricolaAction::MajorImprovement_Fireplace_2);
                         }
                         if self.available_improvements.contains(&MajorImprovement::Fireplace_3) {
-                            actions.push(AgricolaAction::MajorImprovement_Fireplace_3 as u32);
+                            actions.push(AgricolaAction::MajorImprovement_Fireplace_3);
                         }
                         if self.available_improvements.contains(&MajorImprovement::CookingHearth_4) {
-                            actions.push(AgricolaAction::MajorImprovement_CookingHearth_4 as u32);
+                            actions.push(AgricolaAction::MajorImprovement_CookingHearth_4);
                         }
                         if self.available_improvements.contains(&MajorImprovement::CookingHearth_5) {
-                            actions.push(AgricolaAction::MajorImprovement_CookingHearth_5 as u32);
+                            actions.push(AgricolaAction::MajorImprovement_CookingHearth_5);
                         }
                         if self.available_improvements.contains(&MajorImprovement::ClayOven) {
-                            actions.push(AgricolaAction::MajorImprovement_ClayOven as u32);
+                            actions.push(AgricolaAction::MajorImprovement_ClayOven);
                         }
                         if self.available_improvements.contains(&MajorImprovement::StoneOven) {
-                            actions.push(AgricolaAction::MajorImprovement_StoneOven as u32);
+                            actions.push(AgricolaAction::MajorImprovement_StoneOven);
                         }
                         if self.available_improvements.contains(&MajorImprovement::Pottery) {
-                            actions.push(AgricolaAction::MajorImprovement_Pottery as u32);
+                            actions.push(AgricolaAction::MajorImprovement_Pottery);
                         }
                         if self.available_improvements.contains(&MajorImprovement::Joinery) {
-                            actions.push(AgricolaAction::MajorImprovement_Joinery as u32);
+                            actions.push(AgricolaAction::MajorImprovement_Joinery);
                         }
                         if self.available_improvements.contains(&MajorImprovement::BasketmakersWorkshop) {
-                            actions.push(AgricolaAction::MajorImprovement_BasketmakersWorkshop as u32);
+                            actions.push(AgricolaAction::MajorImprovement_BasketmakersWorkshop);
                         }
                     },
-                    &AgricolaTile::Sheep  => actions.push(AgricolaAction::Sheep as u32),
-                    &AgricolaTile::FamilyGrowth  => actions.push(AgricolaAction::FamilyGrowth as u32),
-                    &AgricolaTile::Stone_1  => actions.push(AgricolaAction::Stone_1 as u32),
+                    &AgricolaTile::Sheep  => actions.push(AgricolaAction::Sheep),
+                    &AgricolaTile::FamilyGrowth  => actions.push(AgricolaAction::FamilyGrowth),
+                    &AgricolaTile::Stone_1  => actions.push(AgricolaAction::Stone_1),
                     &AgricolaTile::Renovation_MajorImprovement  => {
                         if self.available_improvements.contains(&MajorImprovement::Fireplace_2) {
-                            actions.push(AgricolaAction::Renovation_MajorImprovement_Fireplace_2 as u32);
+                            actions.push(AgricolaAction::Renovation_MajorImprovement_Fireplace_2);
                         }
                         if self.available_improvements.contains(&MajorImprovement::Fireplace_3) {
-                            actions.push(AgricolaAction::Renovation_MajorImprovement_Fireplace_3 as u32);
+                            actions.push(AgricolaAction::Renovation_MajorImprovement_Fireplace_3);
                         }
                         if self.available_improvements.contains(&MajorImprovement::CookingHearth_4) {
-                            actions.push(AgricolaAction::Renovation_MajorImprovement_CookingHearth_4 as u32);
+                            actions.push(AgricolaAction::Renovation_MajorImprovement_CookingHearth_4);
                         }
                         if self.available_improvements.contains(&MajorImprovement::CookingHearth_5) {
-                            actions.push(AgricolaAction::Renovation_MajorImprovement_CookingHearth_5 as u32);
+                            actions.push(AgricolaAction::Renovation_MajorImprovement_CookingHearth_5);
                         }
                         if self.available_improvements.contains(&MajorImprovement::ClayOven) {
-                            actions.push(AgricolaAction::Renovation_MajorImprovement_ClayOven as u32);
+                            actions.push(AgricolaAction::Renovation_MajorImprovement_ClayOven);
                         }
                         if self.available_improvements.contains(&MajorImprovement::StoneOven) {
-                            actions.push(AgricolaAction::Renovation_MajorImprovement_StoneOven as u32);
+                            actions.push(AgricolaAction::Renovation_MajorImprovement_StoneOven);
                         }
                         if self.available_improvements.contains(&MajorImprovement::Pottery) {
-                            actions.push(AgricolaAction::Renovation_MajorImprovement_Pottery as u32);
+                            actions.push(AgricolaAction::Renovation_MajorImprovement_Pottery);
                         }
                         if self.available_improvements.contains(&MajorImprovement::Joinery) {
-                            actions.push(AgricolaAction::Renovation_MajorImprovement_Joinery as u32);
+                            actions.push(AgricolaAction::Renovation_MajorImprovement_Joinery);
                         }
                         if self.available_improvements.contains(&MajorImprovement::BasketmakersWorkshop) {
-                            actions.push(AgricolaAction::Renovation_MajorImprovement_BasketmakersWorkshop as u32);
+                            actions.push(AgricolaAction::Renovation_MajorImprovement_BasketmakersWorkshop);
                         }
                         if self.available_improvements.contains(&MajorImprovement::Well) {
-                            actions.push(AgricolaAction::Renovation_MajorImprovement_Well as u32);
+                            actions.push(AgricolaAction::Renovation_MajorImprovement_Well);
                         }
                     },
-                    &AgricolaTile::Vegetable  => actions.push(AgricolaAction::Vegetable as u32),
-                    &AgricolaTile::Boar  => actions.push(AgricolaAction::Boar as u32),
-                    &AgricolaTile::Cattle  => actions.push(AgricolaAction::Cattle as u32),
-                    &AgricolaTile::Stone_2  => actions.push(AgricolaAction::Stone_2 as u32),
+                    &AgricolaTile::Vegetable  => actions.push(AgricolaAction::Vegetable),
+                    &AgricolaTile::Boar  => actions.push(AgricolaAction::Boar),
+                    &AgricolaTile::Cattle  => actions.push(AgricolaAction::Cattle),
+                    &AgricolaTile::Stone_2  => actions.push(AgricolaAction::Stone_2),
                     &AgricolaTile::Plow_Sow  => {
-                        actions.push(AgricolaAction::Plow_Sow as u32);
-                        actions.push(AgricolaAction::Plow_NoSow as u32);
-                        actions.push(AgricolaAction::Sow_NoPlow as u32);
+                        actions.push(AgricolaAction::Plow_Sow);
+                        actions.push(AgricolaAction::Plow_NoSow);
+                        actions.push(AgricolaAction::Sow_NoPlow);
                     },
-                    &AgricolaTile::FamilyGrowth_NoSpace  => actions.push(AgricolaAction::FamilyGrowth_NoSpace as u32),
-                    &AgricolaTile::Renovation_Fences  => actions.push(AgricolaAction::Renovation_Fences as u32),
+                    &AgricolaTile::FamilyGrowth_NoSpace  => actions.push(AgricolaAction::FamilyGrowth_NoSpace),
+                    &AgricolaTile::Renovation_Fences  => actions.push(AgricolaAction::Renovation_Fences),
                 }
             }
         }
 
+        // The board's HashMap iterates in a different order every run, sort so that a seeded
+        // game picks the same actions
+        actions.sort();
         actions
     }
 
@@ -147,30 +237,35 @@ impl State for AgricolaState {
         self.player_just_moved
     }
 
-    fn get_action_strings(&self) -> Vec<String> {
-        let mut strings = Vec::new();
-        for action in self.get_actions() {
-            strings.push(format!("{:?}", AgricolaAction::from_u32(action).unwrap()));
+    fn do_action(&mut self, action: AgricolaAction, rng: &mut GameRng) {
+        if let AgricolaAction::Draw(ref card) = action {
+            if !self.pending_draw {
+                panic!("Drawing {:?} while no action card is due", card);
+            }
+            self.board.draw(card);
+            self.pending_draw = false;
+            return;
+        }
+
+        if self.pending_decision.is_some() {
+            self.decide(action, rng);
+            return;
         }
-        strings
-    }
 
-    fn do_action(&mut self, action: u32) {
         if self.players[self.current_player].actions == 0 {
             panic!("Oh noes.. attempting to play a piece with no actions. :(");
         }
 
-        // println!("[R:{} P:{}] Action: {} {:?}", self.rounds, self.current_player, action, AgricolaAction::from_u32(action));
+        // println!("[R:{} P:{}] Action: {:?}", self.rounds, self.current_player, action);
         let self_clone = self.clone();
         let player_index = self.current_player;
         let num_players = self.players.len();
         let mut action_taken = String::from("");
         {
             let mut player = &mut self.players[player_index];
-            let agricola_action = AgricolaAction::from_u32(action);
             let mut curr_tile;
-            match agricola_action {
-                Some(AgricolaAction::Grain) => {
+            match action {
+                AgricolaAction::Grain => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Grain).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. Grain is already taken", player_index);
@@ -178,7 +273,7 @@ impl State for AgricolaState {
                     player.grains += 1;
                     action_taken = String::from("Grain +1");
                 },
-                Some(AgricolaAction::Wood) => {
+                AgricolaAction::Wood => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Wood).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. Wood is already taken", player_index);
@@ -187,7 +282,7 @@ impl State for AgricolaState {
                     action_taken = format!("Wood +{}", curr_tile.items).to_string();
                     curr_tile.items = 0;
                 },
-                Some(AgricolaAction::Clay) => {
+                AgricolaAction::Clay => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Clay).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. clay is already taken", player_index);
@@ -196,7 +291,7 @@ impl State for AgricolaState {
                     action_taken = format!("Clay +{}", curr_tile.items).to_string();
                     curr_tile.items = 0;
                 },
-                Some(AgricolaAction::Reed) => {
+                AgricolaAction::Reed => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Reed).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. reed is already taken", player_index);
@@ -205,7 +300,7 @@ impl State for AgricolaState {
                     action_taken = format!("Reed +{}", curr_tile.items).to_string();
                     curr_tile.items = 0;
                 },
-                Some(AgricolaAction::Fishing) => {
+                AgricolaAction::Fishing => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Fishing).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. fishing is already taken", player_index);
@@ -214,30 +309,30 @@ impl State for AgricolaState {
                     action_taken = format!("Food (Fishing) +{}", curr_tile.items).to_string();
                     curr_tile.items = 0;
                 },
-                Some(AgricolaAction::DayLaborer_Food_Wood) |
-                Some(AgricolaAction::DayLaborer_Food_Clay) |
-                Some(AgricolaAction::DayLaborer_Food_Reed) |
-                Some(AgricolaAction::DayLaborer_Food_Stone) => {
+                AgricolaAction::DayLaborer_Food_Wood |
+                AgricolaAction::DayLaborer_Food_Clay |
+                AgricolaAction::DayLaborer_Food_Reed |
+                AgricolaAction::DayLaborer_Food_Stone => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::DayLaborer).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. day_laborer is already taken", player_index);
                     }
                     player.food += 2;
                     /*
-                    match agricola_action {
-                        Some(AgricolaAction::DayLaborer_Food_Wood) => {
+                    match action {
+                        AgricolaAction::DayLaborer_Food_Wood => {
                             action_taken = format!("Day Laborer Food +1 Wood +1").to_string();
                             player.wood += 1;
                         },
-                        Some(AgricolaAction::DayLaborer_Food_Clay) => {
+                        AgricolaAction::DayLaborer_Food_Clay => {
                             action_taken = format!("Day Laborer Food +1 Clay +1").to_string();
                             player.clay += 1;
                         },
-                        Some(AgricolaAction::DayLaborer_Food_Reed) => {
+                        AgricolaAction::DayLaborer_Food_Reed => {
                             action_taken = format!("Day Laborer Food +1 Reed +1").to_string();
                             player.reed += 1;
                         },
-                        Some(AgricolaAction::DayLaborer_Food_Stone) => {
+                        AgricolaAction::DayLaborer_Food_Stone => {
                             action_taken = format!("Day Laborer Food +1 Stone +1").to_string();
                             player.stone += 1;
                         },
@@ -246,51 +341,51 @@ impl State for AgricolaState {
                     */
                     action_taken = format!("Day Laborer Food +2").to_string();
                 },
-                Some(AgricolaAction::Sow) |
-                Some(AgricolaAction::BakeBread_NotSow) |
-                Some(AgricolaAction::Sow_BakeBread) => {
+                AgricolaAction::Sow |
+                AgricolaAction::BakeBread_NotSow |
+                AgricolaAction::Sow_BakeBread => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Sow_BakeBread).unwrap());
                     // TODO Bake Bread
-                    match agricola_action {
-                        Some(AgricolaAction::Sow) => {
-                            player.sow();
+                    match action {
+                        AgricolaAction::Sow => {
+                            self.pending_decision = Some(PendingDecision::Sow { bake_bread: false });
                             action_taken = format!("Sow").to_string();
                         },
-                        Some(AgricolaAction::BakeBread_NotSow) => {
+                        AgricolaAction::BakeBread_NotSow => {
                             let food = player.bake_bread();
                             action_taken = format!("Bake Bread (+{}) and not Sow", food).to_string();
                         },
-                        Some(AgricolaAction::Sow_BakeBread) =>  {
-                            player.sow();
-                            let food = player.bake_bread();
-                            action_taken = format!("Sow and Bake Bread (+{})", food).to_string();
+                        AgricolaAction::Sow_BakeBread =>  {
+                            // Bread is baked once the player has finished sowing
+                            self.pending_decision = Some(PendingDecision::Sow { bake_bread: true });
+                            action_taken = format!("Sow and Bake Bread").to_string();
                         },
                         _ => panic!("Should never get here.. Sow and Bake Bread only had 3 choices..")
                     }
                 },
-                Some(AgricolaAction::BuildRoom) |
-                Some(AgricolaAction::BuildStables) |
-                Some(AgricolaAction::BuildRoom_BuildStables) => {
+                AgricolaAction::BuildRoom |
+                AgricolaAction::BuildStables |
+                AgricolaAction::BuildRoom_BuildStables => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::BuildRoom_BuildStables).unwrap());
-                    match agricola_action {
-                        Some(AgricolaAction::BuildRoom) => {
-                            player.build_room();
+                    match action {
+                        AgricolaAction::BuildRoom => {
+                            player.build_room(rng);
                             action_taken = format!("Build Room").to_string();
                         },
-                        Some(AgricolaAction::BuildStables) => {
-                            player.build_stables();
+                        AgricolaAction::BuildStables => {
+                            self.pending_decision = Some(PendingDecision::Stables);
                             action_taken = format!("Build Stables").to_string();
                         },
-                        Some(AgricolaAction::BuildRoom_BuildStables) => {
-                            player.build_room();
-                            player.build_stables();
+                        AgricolaAction::BuildRoom_BuildStables => {
+                            player.build_room(rng);
+                            self.pending_decision = Some(PendingDecision::Stables);
                             action_taken = format!("Build Room and Stables").to_string();
                         },
                         _ => panic!("[BuildRoom_BuildStables] Can never reach here..")
                     }
 
                 },
-                Some(AgricolaAction::StartingPlayer_Food) => {
+                AgricolaAction::StartingPlayer_Food => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::StartingPlayer_Food).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. starting player and food is already taken", player_index);
@@ -300,28 +395,28 @@ impl State for AgricolaState {
                     self.starting_player_token = Some(self.current_player);
                     curr_tile.items = 0;
                 },
-                Some(AgricolaAction::Plow) => {
+                AgricolaAction::Plow => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Plow).unwrap());
-                    player.plow();
+                    player.plow(rng);
                     action_taken = format!("Plow").to_string();
                 },
-                Some(AgricolaAction::BuildStable) |
-                Some(AgricolaAction::BakeBread_NoStable) |
-                Some(AgricolaAction::BuildStable_BakeBread) => {
+                AgricolaAction::BuildStable |
+                AgricolaAction::BakeBread_NoStable |
+                AgricolaAction::BuildStable_BakeBread => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::BuildStable_BakeBread).unwrap());
                     action_taken = format!("Ignored!");
                     /*
-                    match agricola_action {
-                        Some(AgricolaAction::BuildStable) => {
-                            player.build_stable();
+                    match action {
+                        AgricolaAction::BuildStable => {
+                            player.build_stable(rng);
                             action_taken = format!("Build 1 stable").to_string();
                         }
-                        Some(AgricolaAction::BakeBread_NoStable) => {
+                        AgricolaAction::BakeBread_NoStable => {
                             let food = player.bake_bread();
                             action_taken = format!("Bake Bread (+{})", food).to_string();
                         }
-                        Some(AgricolaAction::BuildStable_BakeBread) => {
-                            player.build_stable();
+                        AgricolaAction::BuildStable_BakeBread => {
+                            player.build_stable(rng);
                             let food = player.bake_bread();
                             action_taken = format!("Build Stable and Bake Bread (+{})", food).to_string();
                         },
@@ -329,32 +424,33 @@ impl State for AgricolaState {
                     }
                     */
                 },
-                Some(AgricolaAction::Sheep) => {
+                AgricolaAction::Sheep => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Sheep).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. sheep is already taken", player_index);
                     }
                     player.sheep += curr_tile.items;
-                    player.place_animals();
+                    let (sheep, boar, cattle) = player.unhouse_animals();
+                    self.pending_decision = Some(PendingDecision::Animals { sheep, boar, cattle });
                     action_taken = format!("Sheep +{}", curr_tile.items).to_string();
                     curr_tile.items = 0;
                 },
-                Some(AgricolaAction::MajorImprovement_Fireplace_2) |
-                Some(AgricolaAction::MajorImprovement_Fireplace_3) |
-                Some(AgricolaAction::MajorImprovement_CookingHearth_4) |
-                Some(AgricolaAction::MajorImprovement_CookingHearth_5) | 
-                Some(AgricolaAction::MajorImprovement_ClayOven) | 
-                Some(AgricolaAction::MajorImprovement_StoneOven) |
-                Some(AgricolaAction::MajorImprovement_Pottery) |
-                Some(AgricolaAction::MajorImprovement_Joinery) |
-                Some(AgricolaAction::MajorImprovement_BasketmakersWorkshop) => {
+                AgricolaAction::MajorImprovement_Fireplace_2 |
+                AgricolaAction::MajorImprovement_Fireplace_3 |
+                AgricolaAction::MajorImprovement_CookingHearth_4 |
+                AgricolaAction::MajorImprovement_CookingHearth_5 | 
+                AgricolaAction::MajorImprovement_ClayOven | 
+                AgricolaAction::MajorImprovement_StoneOven |
+                AgricolaAction::MajorImprovement_Pottery |
+                AgricolaAction::MajorImprovement_Joinery |
+                AgricolaAction::MajorImprovement_BasketmakersWorkshop => {
                     // println!("{}", self_clone);
-                    // println!("{:?}", agricola_action);
+                    // println!("{:?}", action);
                     //
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::MajorImprovement).unwrap());
 
-                    match agricola_action {
-                        Some(AgricolaAction::MajorImprovement_Fireplace_2) => {
+                    match action {
+                        AgricolaAction::MajorImprovement_Fireplace_2 => {
                             if player.clay >= 2 && self.available_improvements.contains(&MajorImprovement::Fireplace_2) {
                                 player.clay -= 2;
                                 player.improvements.push(MajorImprovement::Fireplace_2);
@@ -373,7 +469,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Tried to buy Fireplace (2), but it wasn't available..").to_string();
                             }
                         },
-                        Some(AgricolaAction::MajorImprovement_Fireplace_3) => {
+                        AgricolaAction::MajorImprovement_Fireplace_3 => {
                             if player.clay >= 3 && self.available_improvements.contains(&MajorImprovement::Fireplace_3) {
                                 player.clay -= 3;
                                 player.improvements.push(MajorImprovement::Fireplace_3);
@@ -392,119 +488,13 @@ impl State for AgricolaState {
                                 action_taken = format!("Tried to buy Fireplace (3), but it wasn't available..").to_string();
                             }
                         },
-                        Some(AgricolaAction::MajorImprovement_CookingHearth_4) => {
-                            let mut action = None;
-
-                            if player.clay >= 4 && (self.available_improvements.contains(&MajorImprovement::Fireplace_2) || 
-                                                    self.available_improvements.contains(&MajorImprovement::Fireplace_3)) {
-                                let coin_toss = ::rand::random::<usize>() % 2; 
-                                match coin_toss {
-                                    0 => { action = Some("pay")},
-                                    1 => { action = Some("exchange")},
-                                    _ => panic!("No other coin toss outcome..?!")
-                                }
-                            } else if player.clay >= 4 {
-                                action = Some("pay");
-                            } else if (self.available_improvements.contains(&MajorImprovement::Fireplace_2) || 
-                                       self.available_improvements.contains(&MajorImprovement::Fireplace_3)) {
-                                action = Some("exchange");
-                            }
-
-                            match action {
-                                Some("pay") => {
-                                    player.improvements.push(MajorImprovement::CookingHearth_4);
-                                    if let Some(index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_4) {
-                                        self.available_improvements.remove(index);
-                                        player.clay -= 4; 
-                                        action_taken = format!("Major Improvement +Cooking Hearth (4) (Pay)").to_string();
-                                    } else {
-                                        action_taken = format!("Tried to buy Cooking Hearth (4), but it wasn't available").to_string();
-                                    }
-                                },
-                                Some("exchange") => {
-                                    if let Some(fireplace_index) = player.improvements.iter().position(|x| *x == MajorImprovement::Fireplace_3) {
-                                        if let Some(cookinghearth_index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_4) {
-                                            self.available_improvements.remove(cookinghearth_index);
-                                            self.available_improvements.push(MajorImprovement::Fireplace_3);
-                                            player.improvements.remove(fireplace_index);
-                                            player.improvements.push(MajorImprovement::CookingHearth_4);
-                                        } else {
-                                            action_taken = format!("Tried to exchange for Cooking Hearth (4), but it wasn't available").to_string();
-                                        }
-                                    } else if let Some(fireplace_index) = player.improvements.iter().position(|x| *x == MajorImprovement::Fireplace_2) {
-                                        if let Some(cookinghearth_index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_4) {
-                                            self.available_improvements.remove(cookinghearth_index);
-                                            self.available_improvements.push(MajorImprovement::Fireplace_2);
-                                            player.improvements.remove(fireplace_index);
-                                            player.improvements.push(MajorImprovement::CookingHearth_4);
-                                            action_taken = format!("Exchange Fireplace (2) -> Cooking Hearth (4)").to_string();
-                                        } else {
-                                            action_taken = format!("Tried to exchange for Cooking Hearth (4), but it wasn't available").to_string();
-                                        }
-                                    }
-                                },
-                                _ => {} // Cannot pay or exchange for Cooking Hearth 4
-                            }
+                        AgricolaAction::MajorImprovement_CookingHearth_4 => {
+                            self.pending_decision = buy_or_choose_payment(player, &mut self.available_improvements, MajorImprovement::CookingHearth_4);
                         },
-                        Some(AgricolaAction::MajorImprovement_CookingHearth_5) => {
-                            let mut action = None;
-
-                            if player.clay >= 5 && (self.available_improvements.contains(&MajorImprovement::Fireplace_2) || 
-                                                    self.available_improvements.contains(&MajorImprovement::Fireplace_3)) {
-                                let coin_toss = ::rand::random::<usize>() % 2; 
-                                match coin_toss {
-                                    0 => { action = Some("pay")},
-                                    1 => { action = Some("exchange")},
-                                    _ => panic!("No other coin toss outcome..?!")
-                                }
-                            } else if player.clay >= 5 {
-                                action = Some("pay");
-                            } else if (self.available_improvements.contains(&MajorImprovement::Fireplace_2) || 
-                                       self.available_improvements.contains(&MajorImprovement::Fireplace_3)) {
-                                action = Some("exchange");
-                            }
-
-                            match action {
-                                Some("pay") => {
-                                    player.improvements.push(MajorImprovement::CookingHearth_5);
-                                    if let Some(index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_5) {
-                                        player.clay -= 5; 
-                                        self.available_improvements.remove(index);
-                                        action_taken = format!("Major Improvement +Cooking Hearth (5) (Pay)").to_string();
-                                    } else {
-                                        // Cooking Hearth 5 is already taken
-                                        action_taken = format!("Tried to buy Cooking Hearth (5), but it wasn't available").to_string();
-                                    }
-                                },
-                                Some("exchange") => {
-                                    if let Some(fireplace_index) = player.improvements.iter().position(|x| *x == MajorImprovement::Fireplace_3) {
-                                        if let Some(cookinghearth_index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_5) {
-                                            self.available_improvements.remove(cookinghearth_index);
-                                            self.available_improvements.push(MajorImprovement::Fireplace_3);
-                                            player.improvements.remove(fireplace_index);
-                                            player.improvements.push(MajorImprovement::CookingHearth_5);
-                                            action_taken = format!("Major Improvement Fireplace (3) -> Cooking Hearth (5)").to_string();
-                                        } else {
-                                            // Cooking Hearth 5 is already taken
-                                            action_taken = format!("Tried to buy Cooking Hearth (5), but it wasn't available").to_string();
-                                        }
-                                    } else if let Some(fireplace_index) = player.improvements.iter().position(|x| *x == MajorImprovement::Fireplace_2) {
-                                        if let Some(cookinghearth_index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_5) {
-                                            self.available_improvements.remove(cookinghearth_index);
-                                            self.available_improvements.push(MajorImprovement::Fireplace_2);
-                                            player.improvements.remove(fireplace_index);
-                                            player.improvements.push(MajorImprovement::CookingHearth_5);
-                                            action_taken = format!("Major Improvement Fireplace (2) -> Cooking Hearth (5)").to_string();
-                                        } else {
-                                            // Cooking Hearth 5 is already taken
-                                            action_taken = format!("Tried to buy Cooking Hearth (5), but it wasn't available").to_string();
-                                        }
-                                    }
-                                },
-                                _ => {} // Cannot pay or exchange for Cooking Hearth 5
-                            }
+                        AgricolaAction::MajorImprovement_CookingHearth_5 => {
+                            self.pending_decision = buy_or_choose_payment(player, &mut self.available_improvements, MajorImprovement::CookingHearth_5);
                         },
-                        Some(AgricolaAction::MajorImprovement_ClayOven) => {
+                        AgricolaAction::MajorImprovement_ClayOven => {
                             if player.clay >= 3 && player.stone >= 1 && self.available_improvements.contains(&MajorImprovement::ClayOven) {
                                 if let Some(index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::ClayOven) {
                                     self.available_improvements.remove(index);
@@ -528,7 +518,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Tried to buy Clay Oven, but it wasn't available..").to_string();
                             }
                         },
-                        Some(AgricolaAction::MajorImprovement_StoneOven) => {
+                        AgricolaAction::MajorImprovement_StoneOven => {
                             if player.clay >= 1 && player.stone >= 3 && self.available_improvements.contains(&MajorImprovement::StoneOven) {
                                 if let Some(index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::StoneOven) {
                                     self.available_improvements.remove(index);
@@ -550,7 +540,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Tried to buy Stone Oven, but it wasn't available..").to_string();
                             }
                         },
-                        Some(AgricolaAction::MajorImprovement_Pottery) => {
+                        AgricolaAction::MajorImprovement_Pottery => {
                             if player.clay >= 2 && player.stone >= 2 && self.available_improvements.contains(&MajorImprovement::Pottery) {
                                 player.clay -= 2;
                                 player.stone -= 2;
@@ -563,7 +553,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Major Improvement - Pottery").to_string();
                             }
                         },
-                        Some(AgricolaAction::MajorImprovement_Joinery) => {
+                        AgricolaAction::MajorImprovement_Joinery => {
                             if player.wood >= 2 && player.stone >= 2 && self.available_improvements.contains(&MajorImprovement::Joinery) {
                                 player.wood -= 2;
                                 player.stone -= 2;
@@ -576,7 +566,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Major Improvement - Joinery").to_string();
                             }
                         },
-                        Some(AgricolaAction::MajorImprovement_BasketmakersWorkshop) => {
+                        AgricolaAction::MajorImprovement_BasketmakersWorkshop => {
                             if player.reed >= 2 && player.stone >= 2 && self.available_improvements.contains(&MajorImprovement::BasketmakersWorkshop) {
                                 player.reed -= 2;
                                 player.stone -= 2;
@@ -589,7 +579,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Major Improvement - BasketmakersWorkshop").to_string();
                             }
                         },
-                        Some(AgricolaAction::MajorImprovement_Well) => {
+                        AgricolaAction::MajorImprovement_Well => {
                             if player.wood >= 1 && player.stone >= 3 && self.available_improvements.contains(&MajorImprovement::Well) {
                                 player.wood -= 1;
                                 player.stone -= 3;
@@ -606,16 +596,15 @@ impl State for AgricolaState {
                         },
                         _ => panic!("No other actions available for major improvements..")
                     }
-                    action_taken = format!("Major Improvement +{:?}", agricola_action).to_string();
+                    action_taken = format!("Major Improvement +{:?}", action).to_string();
 
                 },
-                Some(AgricolaAction::Fences) => {
+                AgricolaAction::Fences => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Fences).unwrap());
-                    let fences_built = player.make_pastures();
-                    // player.fences += fences_built;
-                    action_taken = format!("Fences +{}", fences_built).to_string();
+                    self.pending_decision = Some(PendingDecision::Fences);
+                    action_taken = format!("Fences").to_string();
                 },
-                Some(AgricolaAction::FamilyGrowth) => {
+                AgricolaAction::FamilyGrowth => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::FamilyGrowth).unwrap());
                     let num_rooms = player.player_mat.tiles.iter()
                                                            .filter(|t| t.house.is_some())
@@ -625,7 +614,7 @@ impl State for AgricolaState {
                     }
                     action_taken = format!("Family Growth").to_string();
                 },
-                Some(AgricolaAction::Stone_1) => {
+                AgricolaAction::Stone_1 => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Stone_1).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. first Stone is already taken", player_index);
@@ -634,16 +623,16 @@ impl State for AgricolaState {
                     action_taken = format!("(First) Stone +{}", curr_tile.items).to_string();
                     curr_tile.items = 0;
                 },
-                Some(AgricolaAction::Renovation_MajorImprovement_Fireplace_2) |
-                Some(AgricolaAction::Renovation_MajorImprovement_Fireplace_3) |
-                Some(AgricolaAction::Renovation_MajorImprovement_CookingHearth_4) |
-                Some(AgricolaAction::Renovation_MajorImprovement_CookingHearth_5) |
-                Some(AgricolaAction::Renovation_MajorImprovement_ClayOven) |
-                Some(AgricolaAction::Renovation_MajorImprovement_StoneOven) |
-                Some(AgricolaAction::Renovation_MajorImprovement_Pottery) |
-                Some(AgricolaAction::Renovation_MajorImprovement_Joinery) |
-                Some(AgricolaAction::Renovation_MajorImprovement_BasketmakersWorkshop) |
-                Some(AgricolaAction::Renovation_MajorImprovement_Well) => {
+                AgricolaAction::Renovation_MajorImprovement_Fireplace_2 |
+                AgricolaAction::Renovation_MajorImprovement_Fireplace_3 |
+                AgricolaAction::Renovation_MajorImprovement_CookingHearth_4 |
+                AgricolaAction::Renovation_MajorImprovement_CookingHearth_5 |
+                AgricolaAction::Renovation_MajorImprovement_ClayOven |
+                AgricolaAction::Renovation_MajorImprovement_StoneOven |
+                AgricolaAction::Renovation_MajorImprovement_Pottery |
+                AgricolaAction::Renovation_MajorImprovement_Joinery |
+                AgricolaAction::Renovation_MajorImprovement_BasketmakersWorkshop |
+                AgricolaAction::Renovation_MajorImprovement_Well => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Renovation_MajorImprovement).unwrap());
 
                     let num_rooms = player.player_mat.tiles.iter()
@@ -669,8 +658,8 @@ impl State for AgricolaState {
                         HouseType::Stone => {}
                     }
 
-                    match agricola_action {
-                        Some(AgricolaAction::Renovation_MajorImprovement_Fireplace_2) => {
+                    match action {
+                        AgricolaAction::Renovation_MajorImprovement_Fireplace_2 => {
                             if player.clay >= 2 && self.available_improvements.contains(&MajorImprovement::Fireplace_2) {
                                 player.clay -= 2;
                                 player.improvements.push(MajorImprovement::Fireplace_2);
@@ -682,7 +671,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Renovation and Major Improvement +Fireplace (2)").to_string();
                             }
                         },
-                        Some(AgricolaAction::Renovation_MajorImprovement_Fireplace_3) => {
+                        AgricolaAction::Renovation_MajorImprovement_Fireplace_3 => {
                             if player.clay >= 3 && self.available_improvements.contains(&MajorImprovement::Fireplace_3) {
                                 player.clay -= 3;
                                 player.improvements.push(MajorImprovement::Fireplace_3);
@@ -694,115 +683,13 @@ impl State for AgricolaState {
                                 action_taken = format!("Renovation and Major Improvement +Fireplace (3)").to_string();
                             }
                         },
-                        Some(AgricolaAction::Renovation_MajorImprovement_CookingHearth_4) => {
-                            let mut action = None;
-
-                            if player.clay >= 4 && (self.available_improvements.contains(&MajorImprovement::Fireplace_2) || 
-                                                    self.available_improvements.contains(&MajorImprovement::Fireplace_3)) {
-                                let coin_toss = ::rand::random::<usize>() % 2; 
-                                match coin_toss {
-                                    0 => { action = Some("pay")},
-                                    1 => { action = Some("exchange")},
-                                    _ => panic!("No other coin toss outcome..?!")
-                                }
-                            } else if player.clay >= 4 {
-                                action = Some("pay");
-                            } else if (self.available_improvements.contains(&MajorImprovement::Fireplace_2) || 
-                                       self.available_improvements.contains(&MajorImprovement::Fireplace_3)) {
-                                action = Some("exchange");
-                            }
-
-                            match action {
-                                Some("pay") => {
-                                    player.improvements.push(MajorImprovement::CookingHearth_4);
-                                    if let Some(index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_4) {
-                                        player.clay -= 4; 
-                                        self.available_improvements.remove(index);
-                                    } else {
-                                        // Cooking Hearth 4 already taken
-                                    }
-                                    action_taken = format!("Renovation and Major Improvement +Cooking Hearth (4) (Pay)").to_string();
-                                },
-                                Some("exchange") => {
-                                    if let Some(fireplace_index) = player.improvements.iter().position(|x| *x == MajorImprovement::Fireplace_3) {
-                                        if let Some(cookinghearth_index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_4) {
-                                            self.available_improvements.remove(cookinghearth_index);
-                                            self.available_improvements.push(MajorImprovement::Fireplace_3);
-                                            player.improvements.remove(fireplace_index);
-                                            player.improvements.push(MajorImprovement::CookingHearth_4);
-                                        } else {
-                                            // Cooking Hearth 4 is already taken
-                                        }
-                                    } else if let Some(fireplace_index) = player.improvements.iter().position(|x| *x == MajorImprovement::Fireplace_2) {
-                                        if let Some(cookinghearth_index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_4) {
-                                            self.available_improvements.remove(cookinghearth_index);
-                                            self.available_improvements.push(MajorImprovement::Fireplace_2);
-                                            player.improvements.remove(fireplace_index);
-                                            player.improvements.push(MajorImprovement::CookingHearth_4);
-                                        } else {
-                                            // Cooking Hearth 4 is already taken
-                                        }
-                                    }
-                                    action_taken = format!("Renovation and Major Improvement +Cooking Hearth (4) (Exchange)").to_string();
-                                },
-                                _ => {} // Cannot pay or exchange for Cooking Hearth 4
-                            }
+                        AgricolaAction::Renovation_MajorImprovement_CookingHearth_4 => {
+                            self.pending_decision = buy_or_choose_payment(player, &mut self.available_improvements, MajorImprovement::CookingHearth_4);
                         },
-                        Some(AgricolaAction::Renovation_MajorImprovement_CookingHearth_5) => {
-                            let mut action = None;
-
-                            if player.clay >= 5 && (self.available_improvements.contains(&MajorImprovement::Fireplace_2) || 
-                                                    self.available_improvements.contains(&MajorImprovement::Fireplace_3)) {
-                                let coin_toss = ::rand::random::<usize>() % 2; 
-                                match coin_toss {
-                                    0 => { action = Some("pay")},
-                                    1 => { action = Some("exchange")},
-                                    _ => panic!("No other coin toss outcome..?!")
-                                }
-                            } else if player.clay >= 5 {
-                                action = Some("pay");
-                            } else if (self.available_improvements.contains(&MajorImprovement::Fireplace_2) || 
-                                       self.available_improvements.contains(&MajorImprovement::Fireplace_3)) {
-                                action = Some("exchange");
-                            }
-
-                            match action {
-                                Some("pay") => {
-                                    player.clay -= 5; 
-                                    player.improvements.push(MajorImprovement::CookingHearth_5);
-                                    if let Some(index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_5) {
-                                        self.available_improvements.remove(index);
-                                    } else {
-                                        // Cooking Hearth 5 already taken
-                                    }
-                                    action_taken = format!("Renovation and Major Improvement +Cooking Hearth (5) (Pay)").to_string();
-                                },
-                                Some("exchange") => {
-                                    if let Some(fireplace_index) = player.improvements.iter().position(|x| *x == MajorImprovement::Fireplace_3) {
-                                        if let Some(cookinghearth_index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_5) {
-                                            self.available_improvements.remove(cookinghearth_index);
-                                            self.available_improvements.push(MajorImprovement::Fireplace_3);
-                                            player.improvements.remove(fireplace_index);
-                                            player.improvements.push(MajorImprovement::CookingHearth_5);
-                                        } else {
-                                            // Cooking Hearth 5 is already taken
-                                        }
-                                    } else if let Some(fireplace_index) = player.improvements.iter().position(|x| *x == MajorImprovement::Fireplace_2) {
-                                        if let Some(cookinghearth_index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::CookingHearth_5) {
-                                            self.available_improvements.remove(cookinghearth_index);
-                                            self.available_improvements.push(MajorImprovement::Fireplace_2);
-                                            player.improvements.remove(fireplace_index);
-                                            player.improvements.push(MajorImprovement::CookingHearth_5);
-                                        } else {
-                                            // Cooking Hearth 5 is already taken
-                                        }
-                                    }
-                                    action_taken = format!("Renovation and Major Improvement +Cooking Hearth (5) (Exchange)").to_string();
-                                },
-                                _ => {} // Cannot pay or exchange for Cooking Hearth 5
-                            }
+                        AgricolaAction::Renovation_MajorImprovement_CookingHearth_5 => {
+                            self.pending_decision = buy_or_choose_payment(player, &mut self.available_improvements, MajorImprovement::CookingHearth_5);
                         },
-                        Some(AgricolaAction::Renovation_MajorImprovement_ClayOven) => {
+                        AgricolaAction::Renovation_MajorImprovement_ClayOven => {
                             if player.clay >= 3 && player.stone >= 1 && self.available_improvements.contains(&MajorImprovement::ClayOven) {
                                 if let Some(index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::ClayOven) {
                                     self.available_improvements.remove(index);
@@ -817,7 +704,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Renovation and Major Improvement - Clay Oven").to_string();
                             }
                         },
-                        Some(AgricolaAction::Renovation_MajorImprovement_StoneOven) => {
+                        AgricolaAction::Renovation_MajorImprovement_StoneOven => {
                             if player.clay >= 1 && player.stone >= 3 && self.available_improvements.contains(&MajorImprovement::StoneOven) {
                                 if let Some(index) = self.available_improvements.iter().position(|x| *x == MajorImprovement::StoneOven) {
                                     self.available_improvements.remove(index);
@@ -832,7 +719,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Renovation and Major Improvement - Stone Oven").to_string();
                             }
                         },
-                        Some(AgricolaAction::Renovation_MajorImprovement_Pottery) => {
+                        AgricolaAction::Renovation_MajorImprovement_Pottery => {
                             if player.clay >= 2 && player.stone >= 2 && self.available_improvements.contains(&MajorImprovement::Pottery) {
                                 player.clay -= 2;
                                 player.stone -= 2;
@@ -845,7 +732,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Renovation and Major Improvement - Pottery").to_string();
                             }
                         },
-                        Some(AgricolaAction::Renovation_MajorImprovement_Joinery) => {
+                        AgricolaAction::Renovation_MajorImprovement_Joinery => {
                             if player.wood >= 2 && player.stone >= 2 && self.available_improvements.contains(&MajorImprovement::Joinery) {
                                 player.wood -= 2;
                                 player.stone -= 2;
@@ -858,7 +745,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Renovation and Major Improvement - Joinery").to_string();
                             }
                         },
-                        Some(AgricolaAction::Renovation_MajorImprovement_BasketmakersWorkshop) => {
+                        AgricolaAction::Renovation_MajorImprovement_BasketmakersWorkshop => {
                             if player.reed >= 2 && player.stone >= 2 && self.available_improvements.contains(&MajorImprovement::BasketmakersWorkshop) {
                                 player.reed -= 2;
                                 player.stone -= 2;
@@ -871,7 +758,7 @@ impl State for AgricolaState {
                                 action_taken = format!("Renovation and Major Improvement - BasketmakersWorkshop").to_string();
                             }
                         },
-                        Some(AgricolaAction::Renovation_MajorImprovement_Well) => {
+                        AgricolaAction::Renovation_MajorImprovement_Well => {
                             if player.wood >= 1 && player.stone >= 3 && self.available_improvements.contains(&MajorImprovement::Well) {
                                 player.wood -= 1;
                                 player.stone -= 3;
@@ -889,9 +776,9 @@ impl State for AgricolaState {
                         _ => panic!("No other actions available for major improvements..")
                     }
 
-                    action_taken = format!("{:?}", agricola_action).to_string();
+                    action_taken = format!("{:?}", action).to_string();
                 },
-                Some(AgricolaAction::Vegetable) => {
+                AgricolaAction::Vegetable => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Vegetable).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. vegetable is already taken", player_index);
@@ -899,27 +786,29 @@ impl State for AgricolaState {
                     player.vegetables += 1;
                     action_taken = format!("Vegetable +1").to_string();
                 },
-                Some(AgricolaAction::Boar) => {
+                AgricolaAction::Boar => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Boar).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. Boar is already taken", player_index);
                     }
                     player.boar += curr_tile.items;
-                    player.place_animals();
+                    let (sheep, boar, cattle) = player.unhouse_animals();
+                    self.pending_decision = Some(PendingDecision::Animals { sheep, boar, cattle });
                     action_taken = format!("Boar +{}", curr_tile.items).to_string();
                     curr_tile.items = 0;
                 },
-                Some(AgricolaAction::Cattle) => {
+                AgricolaAction::Cattle => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Cattle).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. Cattle is already taken", player_index);
                     }
                     player.cattle += curr_tile.items;
-                    player.place_animals();
+                    let (sheep, boar, cattle) = player.unhouse_animals();
+                    self.pending_decision = Some(PendingDecision::Animals { sheep, boar, cattle });
                     action_taken = format!("Cattle +{}", curr_tile.items).to_string();
                     curr_tile.items = 0;
                 },
-                Some(AgricolaAction::Stone_2) => {
+                AgricolaAction::Stone_2 => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Stone_2).unwrap());
                     if !curr_tile.occupied.is_none() {
                         panic!("Player {} is bad.. second Stone is already taken", player_index);
@@ -928,33 +817,33 @@ impl State for AgricolaState {
                     action_taken = format!("(Second) Stone +{}", curr_tile.items).to_string();
                     curr_tile.items = 0;
                 },
-                Some(AgricolaAction::Plow_NoSow) |
-                Some(AgricolaAction::Sow_NoPlow) |
-                Some(AgricolaAction::Plow_Sow) => {
+                AgricolaAction::Plow_NoSow |
+                AgricolaAction::Sow_NoPlow |
+                AgricolaAction::Plow_Sow => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Plow_Sow).unwrap());
-                    match agricola_action {
-                        Some(AgricolaAction::Plow_NoSow) => {
-                            player.plow();
+                    match action {
+                        AgricolaAction::Plow_NoSow => {
+                            player.plow(rng);
                             action_taken = format!("Plow but No Sow").to_string();
                         }
-                        Some(AgricolaAction::Sow_NoPlow) => {
-                            player.sow();
+                        AgricolaAction::Sow_NoPlow => {
+                            self.pending_decision = Some(PendingDecision::Sow { bake_bread: false });
                             action_taken = format!("Sow but No Plow").to_string();
                         }
-                        Some(AgricolaAction::Plow_Sow) => {
-                            player.plow();
-                            player.sow();
+                        AgricolaAction::Plow_Sow => {
+                            player.plow(rng);
+                            self.pending_decision = Some(PendingDecision::Sow { bake_bread: false });
                             action_taken = format!("Plow and Sow").to_string();
                         },
                         _ => panic!("[Plow_Sow] Can never reach here..")
                     }
                 },
-                Some(AgricolaAction::FamilyGrowth_NoSpace) => {
+                AgricolaAction::FamilyGrowth_NoSpace => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::FamilyGrowth_NoSpace).unwrap());
                     player.children = 1;
                     action_taken = format!("Family Growth with no space").to_string();
                 },
-                Some(AgricolaAction::Renovation_Fences) => {
+                AgricolaAction::Renovation_Fences => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Renovation_Fences).unwrap());
                     let num_rooms = player.player_mat.tiles.iter()
                                                            .filter(|t| t.house.is_some())
@@ -978,8 +867,8 @@ impl State for AgricolaState {
                         HouseType::Stone => {}
                     }
 
-                    let fences_built = player.make_pastures();
-                    action_taken = format!("Renovation and Fences +{}", fences_built).to_string();
+                    self.pending_decision = Some(PendingDecision::Fences);
+                    action_taken = format!("Renovation and Fences").to_string();
                 },
                 _ => {
                     curr_tile = &mut *(self.board.tiles.get_mut(&AgricolaTile::Plow).unwrap());
@@ -987,7 +876,7 @@ impl State for AgricolaState {
                 },
             }
 
-            // println!("Action: {:?} Curr_tile: {:?}", agricola_action, curr_tile);
+            // println!("Action: {:?} Curr_tile: {:?}", action, curr_tile);
             curr_tile.occupied = Some(self.current_player);
 
             player.actions -= 1;
@@ -996,44 +885,142 @@ impl State for AgricolaState {
 
         self.add_action(player_index, action_taken);
 
-        /*
-        * Since players can have different number of actions, we need to loop through
-        * all players looking for the next player with actions.
-        */ 
-        let orig_player = self.current_player;
-        for curr_player_index in player_index+1..player_index+1+num_players { 
-            let player_index = curr_player_index % num_players;
-            let curr_player = &self.players[player_index];
-            if curr_player.actions > 0 {
-                // Found a player with actions left
-                self.current_player = player_index;
-                break;
+        // The turn stays with the player until they have made the choices the action needs
+        if self.pending_decision.is_some() {
+            if self.decision_open() {
+                return;
             }
+            self.finish_decision();
         }
 
-        // self.current_player didn't change, therefore no players have available actions
-        if self.current_player == orig_player {
-            self.end_round();
-        }
+        self.next_player(rng);
+    }
+
+    fn in_pending_decision(&self) -> bool {
+        self.pending_decision.is_some()
     }
 
-    fn get_result(&self, player: usize) -> f32 {
+    fn get_rewards(&self) -> Vec<f32> {
         let mut scores = Vec::new();
         for player in &self.players {
             scores.push(player.score(false));
         }
 
-        if scores[player] == *scores.iter().max().unwrap() {
-            return 1.0
-        } else {
-            return 0.0
+        // Every player tied for the highest score wins
+        let best_score = *scores.iter().max().unwrap();
+        scores.iter()
+              .map(|&score| if score == best_score { 1.0 } else { 0.0 })
+              .collect()
+    }
+
+    /// Drawing the next round's action card, every card of the current stage is equally likely
+    fn get_chance_outcomes(&self) -> Vec<(AgricolaAction, f32)> {
+        if !self.pending_draw {
+            return Vec::new();
+        }
+
+        let cards = self.board.next_cards();
+        let probability = 1.0 / cards.len() as f32;
+        cards.into_iter().map(|card| (AgricolaAction::Draw(card), probability)).collect()
+    }
+}
+
+
+impl Determinizable for AgricolaState {
+    fn get_player_to_move(&self) -> usize {
+        self.current_player
+    }
+
+    fn get_num_players(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Both farms are in the open and the unrevealed action cards are drawn as chance events, so
+    /// there is nothing to hide from either player
+    fn determinize(&self, _observer: usize, _rng: &mut GameRng) -> AgricolaState {
+        self.clone()
+    }
+}
+
+impl StateHash for AgricolaState {
+    /// Hashes what both players can see: the farms, the board and the turn. The logs of actions
+    /// taken and the order of the cards still to be drawn are left out. The hash is written with
+    /// a `StableHasher`, so opening books saved on one machine match on any other.
+    fn state_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        hasher.write(self.players.len() as u64);
+        for player in &self.players {
+            player.stable_hash(&mut hasher);
+        }
+        hasher.write(self.player_just_moved as u64);
+        hasher.write(self.current_player as u64);
+        hasher.write_option(self.starting_player_token.map(|player| player as u64));
+        let mut tiles: Vec<_> = self.board.tiles.iter().collect();
+        tiles.sort_by_key(|&(tile, _)| tile);
+        hasher.write(tiles.len() as u64);
+        for (tile, board_tile) in tiles {
+            hasher.write(tile.clone() as u64);
+            board_tile.stable_hash(&mut hasher);
+        }
+        hasher.write(self.rounds as u64);
+        hasher.write(self.available_improvements.len() as u64);
+        for improvement in &self.available_improvements {
+            hasher.write(improvement.clone() as u64);
         }
+        hasher.write(self.pending_draw as u64);
+        match self.pending_decision {
+            None => hasher.write(0),
+            Some(ref decision) => {
+                hasher.write(1);
+                decision.stable_hash(&mut hasher);
+            }
+        }
+        hasher.write_option(self.well_player.map(|player| player as u64));
+        hasher.write(self.well_food as u64);
+        hasher.finish()
     }
 }
 
+/// Layout for `n` players, `10 + 2n + 24 * (2 + n) + n * PLAYER_FEATURES` values:
+///
+/// - round, waiting on a card draw, sowing, fencing, building stables, housing animals, paying
+///   for a Cooking Hearth, then the sheep, boar and cattle still waiting to be housed
+/// - current player and starting player token, a flag per player each
+/// - the board, see `Board::encode`
+/// - every player in turn order, see `Player::encode`
+impl FeatureEncode for AgricolaState {
+    fn feature_len(&self) -> usize {
+        let players = self.players.len();
+        10 + 2 * players + BOARD_TILES * (2 + players) + players * PLAYER_FEATURES
+    }
+
+    fn features(&self) -> Vec<f32> {
+        let players = self.players.len();
+        let mut features = Vec::with_capacity(self.feature_len());
+        features.push(self.rounds as f32);
+        features.push(self.pending_draw as usize as f32);
+        features.push(matches!(self.pending_decision, Some(PendingDecision::Sow { .. })) as usize as f32);
+        features.push(matches!(self.pending_decision, Some(PendingDecision::Fences)) as usize as f32);
+        features.push(matches!(self.pending_decision, Some(PendingDecision::Stables)) as usize as f32);
+        features.push(matches!(self.pending_decision, Some(PendingDecision::Animals { .. })) as usize as f32);
+        features.push(matches!(self.pending_decision, Some(PendingDecision::CookingHearth(_))) as usize as f32);
+        let waiting = match self.pending_decision {
+            Some(PendingDecision::Animals { sheep, boar, cattle }) => [sheep, boar, cattle],
+            _ => [0, 0, 0]
+        };
+        features.extend(waiting.iter().map(|&count| count as f32));
+        one_hot(Some(self.current_player), players, &mut features);
+        one_hot(self.starting_player_token, players, &mut features);
+        self.board.encode(players, &mut features);
+        for player in &self.players {
+            player.encode(&mut features);
+        }
+        features
+    }
+}
 
 impl AgricolaState {
-    pub fn new(num_players: usize) -> AgricolaState {
+    pub fn new(num_players: usize, rng: &mut GameRng) -> AgricolaState {
         let mut players = Vec::new();
 
         /// Player one gets 2 food while others get 3
@@ -1050,7 +1037,7 @@ impl AgricolaState {
             player_just_moved: 0,
             current_player: 0,
             starting_player_token: None,
-            board: Board::new(),
+            board: Board::new(rng),
             rounds: 1,
             total_rounds: 14,
             actions_taken: Vec::new(),
@@ -1058,12 +1045,140 @@ impl AgricolaState {
                                          MajorImprovement::CookingHearth_5, MajorImprovement::ClayOven, MajorImprovement::StoneOven, 
                                          MajorImprovement::Joinery, MajorImprovement::Pottery, MajorImprovement::BasketmakersWorkshop, 
                                          MajorImprovement::Well),
+            pending_draw: false,
+            pending_decision: None,
             well_player: None,
             well_food: 0
         }
     }
 
-    pub fn end_round(&mut self) {
+    /// Play one choice of the pending decision, handing the turn on once the action is finished
+    fn decide(&mut self, action: AgricolaAction, rng: &mut GameRng) {
+        let player_index = self.current_player;
+        let action_taken;
+        let finished;
+        {
+            let player = &mut self.players[player_index];
+            match action {
+                AgricolaAction::SowGrain => {
+                    player.sow_next_field(Crop::Grain);
+                    action_taken = String::from("Sow Grain");
+                    finished = false;
+                },
+                AgricolaAction::SowVegetable => {
+                    player.sow_next_field(Crop::Vegetable);
+                    action_taken = String::from("Sow Vegetable");
+                    finished = false;
+                },
+                AgricolaAction::FencePasture(tiles) => {
+                    let fences_built = player.fence_pasture(tiles.clone());
+                    action_taken = format!("Fence Pasture {:?} +{}", tiles, fences_built);
+                    finished = false;
+                },
+                AgricolaAction::BuildStableOn(tile) => {
+                    player.build_stable_on(tile);
+                    action_taken = format!("Build Stable on {}", tile);
+                    finished = false;
+                },
+                AgricolaAction::HouseAnimals(ref housing, ref animal) => {
+                    let waiting = match self.pending_decision {
+                        Some(PendingDecision::Animals { ref mut sheep, ref mut boar, ref mut cattle }) => match *animal {
+                            Animal::Sheep => sheep,
+                            Animal::Boar => boar,
+                            Animal::Cattle => cattle
+                        },
+                        _ => panic!("{:?} is not a choice for {:?}", action, self.pending_decision)
+                    };
+                    let housed = player.house_animals(housing, animal.clone(), *waiting);
+                    *waiting -= housed;
+                    action_taken = format!("House {} {:?} in {:?}", housed, animal, housing);
+                    finished = false;
+                },
+                AgricolaAction::PayClay |
+                AgricolaAction::ReturnFireplace => {
+                    let hearth = match self.pending_decision {
+                        Some(PendingDecision::CookingHearth(ref hearth)) => hearth.clone(),
+                        _ => panic!("{:?} is not a choice for {:?}", action, self.pending_decision)
+                    };
+                    buy_cooking_hearth(player, &mut self.available_improvements, hearth.clone(), &action);
+                    action_taken = format!("{:?} for {:?}", action, hearth);
+                    finished = true;
+                },
+                AgricolaAction::FinishSowing |
+                AgricolaAction::FinishFences |
+                AgricolaAction::FinishStables |
+                AgricolaAction::FinishAnimals => {
+                    action_taken = format!("{:?}", action);
+                    finished = true;
+                },
+                _ => panic!("{:?} is not a choice for {:?}", action, self.pending_decision)
+            }
+        }
+        self.add_action(player_index, action_taken);
+
+        if finished || !self.decision_open() {
+            self.finish_decision();
+            self.next_player(rng);
+        }
+    }
+
+    /// The current player has a choice left in the pending decision other than finishing it
+    fn decision_open(&self) -> bool {
+        let player = &self.players[self.current_player];
+        match self.pending_decision {
+            Some(PendingDecision::Sow { .. }) => player.can_sow(),
+            Some(PendingDecision::Fences) => !player.pasture_options().is_empty(),
+            Some(PendingDecision::Stables) => !player.stable_options().is_empty(),
+            Some(PendingDecision::Animals { sheep, boar, cattle }) => {
+                sheep + boar + cattle > 0 && !player.housing_options().is_empty()
+            },
+            Some(PendingDecision::CookingHearth(_)) => true,
+            None => false
+        }
+    }
+
+    /// Complete the action that the pending decision belonged to
+    fn finish_decision(&mut self) {
+        let player_index = self.current_player;
+        match self.pending_decision.take() {
+            Some(PendingDecision::Sow { bake_bread: true }) => {
+                let food = self.players[player_index].bake_bread();
+                self.add_action(player_index, format!("Bake Bread (+{})", food));
+            },
+            Some(PendingDecision::Animals { sheep, boar, cattle }) if sheep + boar + cattle > 0 => {
+                let food = self.players[player_index].turn_away_animals(sheep, boar, cattle);
+                self.add_action(player_index, format!("Turn away {} sheep, {} boar, {} cattle (+{})", sheep, boar, cattle, food));
+            },
+            _ => {}
+        }
+    }
+
+    /// Pass the turn to the next player with actions left, ending the round if nobody has any
+    fn next_player(&mut self, rng: &mut GameRng) {
+        /*
+        * Since players can have different number of actions, we need to loop through
+        * all players looking for the next player with actions.
+        */ 
+        let player_index = self.current_player;
+        let num_players = self.players.len();
+        let orig_player = self.current_player;
+        for curr_player_index in player_index+1..player_index+1+num_players { 
+            let player_index = curr_player_index % num_players;
+            let curr_player = &self.players[player_index];
+            if curr_player.actions > 0 {
+                // Found a player with actions left
+                self.current_player = player_index;
+                break;
+            }
+        }
+
+        // self.current_player didn't change, therefore no players have available actions
+        if self.current_player == orig_player {
+            self.end_round(rng);
+        }
+    }
+
+    pub fn end_round(&mut self, rng: &mut GameRng) {
         // println!("Ending round");
 
         // Set next player
@@ -1080,8 +1195,9 @@ impl AgricolaState {
         }
         self.starting_player_token = None;
 
-        // Reset the board
+        // Reset the board, the next action card is drawn as a chance event
         self.board.reset();
+        self.pending_draw = !self.board.future_tiles.is_empty();
 
         match self.rounds {
             4|7|9|11|13|14 => {
@@ -1167,7 +1283,7 @@ impl AgricolaState {
                                 let mut food_needed = orig_food_needed;
                                 while food_needed > 0 && (temp_state.sheep > 0 || temp_state.boar > 0 || temp_state.cattle > 0 || temp_state.vegetables > 0) {
                                     let possibles = vec!("s", "b", "c", "v", "g");
-                                    let animal = rand::thread_rng().choose(&possibles);
+                                    let animal = rng.choose(&possibles);
                                     match animal {
                                         Some(&"s") => { 
                                             if temp_state.sheep == 0 { continue; }
@@ -1252,7 +1368,7 @@ impl AgricolaState {
                         player.cattle += 1;
                     }
 
-                    player.place_animals();
+                    player.rehouse_animals();
                     let player_display = format!("{}", player);
                     player.actions_taken.push(format!("Breeding Phase:\n{}", player_display));
                 }
@@ -1283,6 +1399,25 @@ impl AgricolaState {
         }
     }
 
+    /// Estimated rewards of an unfinished game from how far ahead or behind the best other player
+    /// each player is on points. Used to score rollouts that are cut short.
+    pub fn evaluate(&self) -> Vec<f32> {
+        let scores: Vec<i32> = self.players.iter().map(|p| p.score(false)).collect();
+
+        scores.iter()
+              .enumerate()
+              .map(|(player, &score)| {
+                  let best_other = scores.iter()
+                                         .enumerate()
+                                         .filter(|&(i, _)| i != player)
+                                         .map(|(_, &s)| s)
+                                         .max()
+                                         .unwrap_or(score);
+                  0.5 + 0.5 * ((score - best_other) as f32 / 10.0).tanh()
+              })
+              .collect()
+    }
+
     pub fn add_action(&mut self, player: usize, action: String) {
         let curr_player = &mut self.players[player];
         let curr_player_display = format!("{}", curr_player);
@@ -1308,6 +1443,50 @@ impl AgricolaState {
     }
 }
 
+/// Buy `hearth` the one way `player` can pay for it, or return the decision between paying clay
+/// and returning a fireplace when they can do both
+fn buy_or_choose_payment(player: &mut Player, available: &mut Vec<MajorImprovement>, hearth: MajorImprovement) -> Option<PendingDecision> {
+    if !available.contains(&hearth) {
+        return None;
+    }
+
+    let can_pay = player.clay >= hearth_cost(&hearth);
+    let can_return = player.improvements.contains(&MajorImprovement::Fireplace_2) ||
+                     player.improvements.contains(&MajorImprovement::Fireplace_3);
+    match (can_pay, can_return) {
+        (true, true) => return Some(PendingDecision::CookingHearth(hearth)),
+        (true, false) => buy_cooking_hearth(player, available, hearth, &AgricolaAction::PayClay),
+        (false, true) => buy_cooking_hearth(player, available, hearth, &AgricolaAction::ReturnFireplace),
+        (false, false) => {}
+    }
+    None
+}
+
+/// Take `hearth` from the supply, paying with `PayClay` or `ReturnFireplace`
+fn buy_cooking_hearth(player: &mut Player, available: &mut Vec<MajorImprovement>, hearth: MajorImprovement, payment: &AgricolaAction) {
+    match *payment {
+        AgricolaAction::PayClay => player.clay -= hearth_cost(&hearth),
+        AgricolaAction::ReturnFireplace => {
+            let index = player.improvements.iter()
+                                           .position(|x| *x == MajorImprovement::Fireplace_3)
+                                           .or_else(|| player.improvements.iter().position(|x| *x == MajorImprovement::Fireplace_2))
+                                           .expect("No fireplace to return");
+            available.push(player.improvements.remove(index));
+        },
+        _ => panic!("{:?} is not a payment for {:?}", payment, hearth)
+    }
+    available.retain(|x| *x != hearth);
+    player.improvements.push(hearth);
+}
+
+/// Clay that `hearth` costs
+fn hearth_cost(hearth: &MajorImprovement) -> usize {
+    match *hearth {
+        MajorImprovement::CookingHearth_4 => 4,
+        _ => 5
+    }
+}
+
 impl Display for AgricolaState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Player Just Played: {}\n", self.player_just_moved + 1);
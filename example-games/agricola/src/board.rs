@@ -18,8 +18,33 @@ pub struct Board {
     // pub future_tiles: Vec<(AgricolaTile, Box<BoardTile>)>, // Non random action draw
 }
 
+/// Number of different action spaces, every one that can ever be on the board
+pub const BOARD_TILES: usize = 24;
+
+impl BoardTile {
+    pub fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_option(self.occupied.map(|player| player as u64));
+        hasher.write(self.items as u64);
+        hasher.write(self.reset_amount as u64);
+    }
+}
+
 impl Board {
-    pub fn new() -> Board {
+    /// Append every action space in `AgricolaTile` order: whether it is on the board yet, the
+    /// items on it, and which of the `players` occupies it as a flag per player
+    pub fn encode(&self, players: usize, features: &mut Vec<f32>) {
+        let mut spaces = vec!(None; BOARD_TILES);
+        for (tile, board_tile) in &self.tiles {
+            spaces[tile.clone() as usize - 1] = Some(board_tile);
+        }
+        for space in spaces {
+            features.push(space.is_some() as usize as f32);
+            features.push(space.map_or(0, |space| space.items) as f32);
+            one_hot(space.and_then(|space| space.occupied), players, features);
+        }
+    }
+
+    pub fn new(rng: &mut GameRng) -> Board {
         let mut future_tiles = Vec::new();
 
         let round_1_tiles = vec!(
@@ -94,7 +119,7 @@ impl Board {
 
         // Insert first action
         if let Some(mut tile_set) = future_tiles.pop() {
-            rand::thread_rng().shuffle(&mut tile_set);
+            rng.shuffle(&mut tile_set);
             if let Some((next_card, next_tile)) = tile_set.pop() {
                 println!("[Board init] Next action: {:?} {:?}", next_card, next_tile);
                 board.insert(next_card, next_tile);
@@ -111,7 +136,7 @@ impl Board {
         }
     }
 
-    /// Add more counters to the board and add the next action card to the board
+    /// Add more counters to the board. The next action card is added separately with `draw`.
     pub fn reset(&mut self) {
         for (name, mut tile) in &mut self.tiles {
             // println!("{:?}: {:?} -> {:?}", name, tile.items, tile.items+tile.reset_amount);
@@ -126,12 +151,22 @@ impl Board {
             self.tiles.insert(next_card, next_tile);
         }
         */
+    }
+
+    /// Action cards that the next draw can turn over, all equally likely
+    pub fn next_cards(&self) -> Vec<AgricolaTile> {
+        match self.future_tiles.last() {
+            Some(tile_set) => tile_set.iter().map(|&(ref card, _)| card.clone()).collect(),
+            None => Vec::new()
+        }
+    }
 
-        // Random action draw
+    /// Take `card` out of the next stage's action cards and add it to the board
+    pub fn draw(&mut self, card: &AgricolaTile) {
         if let Some(mut tile_set) = self.future_tiles.pop() {
-            rand::thread_rng().shuffle(&mut tile_set);
-            if let Some((next_card, next_tile)) = tile_set.pop() {
-                // println!("[Board reset] Next action: {:?} {:?}", next_card, next_tile);
+            if let Some(index) = tile_set.iter().position(|&(ref next_card, _)| next_card == card) {
+                let (next_card, next_tile) = tile_set.remove(index);
+                // println!("[Board draw] Next action: {:?} {:?}", next_card, next_tile);
                 self.tiles.insert(next_card, next_tile);
             }
 
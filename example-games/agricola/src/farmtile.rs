@@ -16,6 +16,9 @@ pub struct FarmTile {
     pub index: usize
 }
 
+/// Number of features `FarmTile::encode` appends
+pub const FARM_TILE_FEATURES: usize = 17;
+
 impl FarmTile {
     pub fn new(index: usize) -> FarmTile {
         FarmTile {
@@ -34,6 +37,41 @@ impl FarmTile {
         }
     }
     
+    /// Append the tile's contents: house (wood, clay, stone flags), stable, pasture, animal
+    /// (sheep, boar, cattle flags), animal count, plowed, crop (grain, vegetable flags), crop count
+    /// and fences (north, south, east, west)
+    pub fn encode(&self, features: &mut Vec<f32>) {
+        one_hot(self.house.clone().map(|house| house as usize), 3, features);
+        features.push(self.stable as usize as f32);
+        features.push(self.pasture as usize as f32);
+        one_hot(self.animal_type.clone().map(|animal| animal as usize), 3, features);
+        features.push(self.animal_count as f32);
+        features.push(self.field.is_some() as usize as f32);
+        let crop = self.field.as_ref().and_then(|field| field.crop.clone());
+        one_hot(crop.map(|crop| crop as usize), 2, features);
+        features.push(self.field.as_ref().map_or(0, |field| field.count) as f32);
+        for &fence in &[self.north_fence, self.south_fence, self.east_fence, self.west_fence] {
+            features.push(fence as usize as f32);
+        }
+    }
+
+    /// Write the tile's contents, the same ones `encode` appends
+    pub fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_option(self.house.clone().map(|house| house as u64));
+        hasher.write(self.stable as u64);
+        hasher.write(self.pasture as u64);
+        hasher.write_option(self.animal_type.clone().map(|animal| animal as u64));
+        hasher.write(self.animal_count as u64);
+        hasher.write(self.field.is_some() as u64);
+        if let Some(ref field) = self.field {
+            hasher.write_option(field.crop.clone().map(|crop| crop as u64));
+            hasher.write(field.count as u64);
+        }
+        for &fence in &[self.north_fence, self.south_fence, self.east_fence, self.west_fence] {
+            hasher.write(fence as u64);
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.house.is_none() && self.field.is_none() && !self.pasture && !self.stable 
     }
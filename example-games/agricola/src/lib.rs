@@ -2,11 +2,13 @@
 
 #[macro_use]
 extern crate lazy_static;
+#[macro_use]
+extern crate serde_derive;
 extern crate boardgameai_rs;
 extern crate rand;
 
 use boardgameai_rs::*;
-use boardgameai_rs::state::State;
+use boardgameai_rs::state::{Determinizable, FeatureEncode, State, StateHash};
 use boardgameai_rs::action::Action;
 // use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
@@ -85,13 +85,24 @@ impl Display for HouseType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Animal {
     Sheep,
     Boar,
     Cattle
 }
 
+/// Somewhere on a farm that animals can be kept
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Housing {
+    /// A fenced pasture, by its index in `Player::pastures`
+    Pasture(usize),
+    /// A stable outside any pasture, by its farm tile
+    Stable(usize),
+    /// The one pet the house has room for
+    House
+}
+
 impl Display for Animal {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -108,3 +119,49 @@ pub enum Crop {
     Vegetable
 }
 
+
+/// FNV-1a over every value written as a little-endian `u64`. Unlike `DefaultHasher`, its output
+/// is the same on every platform and Rust release, so hashes saved to disk, such as the keys of
+/// an opening book, keep matching.
+pub struct StableHasher {
+    hash: u64
+}
+
+impl Default for StableHasher {
+    fn default() -> StableHasher {
+        StableHasher::new()
+    }
+}
+
+impl StableHasher {
+    pub fn new() -> StableHasher {
+        StableHasher { hash: 0xcbf2_9ce4_8422_2325 }
+    }
+
+    pub fn write(&mut self, value: u64) {
+        for byte in value.to_le_bytes().iter() {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Write 0 for `None`, or 1 followed by the value
+    pub fn write_option(&mut self, value: Option<u64>) {
+        match value {
+            None => self.write(0),
+            Some(value) => {
+                self.write(1);
+                self.write(value);
+            }
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// Append `len` flags, with only the one at `index` set
+pub fn one_hot(index: Option<usize>, len: usize, features: &mut Vec<f32>) {
+    features.extend((0..len).map(|i| if Some(i) == index { 1.0 } else { 0.0 }));
+}
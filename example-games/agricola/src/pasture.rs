@@ -15,4 +15,13 @@ impl Pasture {
             tiles: tiles
         }
     }
+
+    pub fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write(self.tiles.len() as u64);
+        for &tile in &self.tiles {
+            hasher.write(tile as u64);
+        }
+        hasher.write(self.stables as u64);
+        hasher.write(self.capacity as u64);
+    }
 }
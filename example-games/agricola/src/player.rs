@@ -1,8 +1,8 @@
 use super::*;
-use rand::{Rng, thread_rng};
+use rand::Rng;
+use std::collections::HashSet;
 use std::fmt;
 use std::fmt::Display;
-use std::collections::HashSet;
 
 #[derive(Debug, Clone)]
 pub struct Player {
@@ -31,6 +31,9 @@ pub struct Player {
     pub improvements: Vec<MajorImprovement>
 }
 
+/// Number of features `Player::encode` appends
+pub const PLAYER_FEATURES: usize = 34 + 15 * FARM_TILE_FEATURES;
+
 impl Player {
     pub fn new(food: usize) -> Player {
         
@@ -64,6 +67,30 @@ impl Player {
         new_player
     }
 
+    /// Write the farm and supplies, leaving out the log of actions taken, so players who reached
+    /// the same farm in a different order hash the same
+    pub fn stable_hash(&self, hasher: &mut StableHasher) {
+        for &count in &[self.food, self.fields, self.grains, self.vegetables, self.wood, self.clay,
+                        self.reed, self.stone, self.sheep, self.cattle, self.boar, self.actions,
+                        self.total_actions] {
+            hasher.write(count as u64);
+        }
+        self.player_mat.stable_hash(hasher);
+        hasher.write(self.house_type.clone() as u64);
+        for &count in &[self.beggers, self.children, self.stables, self.fences] {
+            hasher.write(count as u64);
+        }
+        hasher.write(self.pastures.len() as u64);
+        for pasture in &self.pastures {
+            pasture.stable_hash(hasher);
+        }
+        hasher.write_option(self.pet.clone().map(|animal| animal as u64));
+        hasher.write(self.improvements.len() as u64);
+        for improvement in &self.improvements {
+            hasher.write(improvement.clone() as u64);
+        }
+    }
+
     pub fn score(&self, verbose: bool) -> i32 {
         let mut result: i32 = 0;
         let mut score = -1;
@@ -235,7 +262,7 @@ impl Player {
 
     /// Randomly plow a field if none exists. If a field already exists, plow a random field
     /// connected to an existing field
-    pub fn plow(&mut self) {
+    pub fn plow(&mut self, rng: &mut GameRng) {
         {
             let empty_spaces: Vec<&FarmTile> = self.player_mat.tiles.iter()
                                                                     .filter(|t| t.is_empty() && 
@@ -251,14 +278,14 @@ impl Player {
         }
         if self.fields == 0 {
             loop {
-                let num = thread_rng().gen_range(0, 15);
+                let num = rng.gen_range(0, 15);
                 if self.player_mat.tiles[num].is_empty() {
                     self.player_mat.tiles[num].plow();
                     break;
                 }
             }
         } else {
-            self.player_mat.plow_random_field();
+            self.player_mat.plow_random_field(rng);
         }
         self.fields += 1;
     }
@@ -287,7 +314,7 @@ impl Player {
         }
     }
 
-    pub fn build_room(&mut self) {
+    pub fn build_room(&mut self, rng: &mut GameRng) {
         if !self.can_build_room() {
             return;
         }
@@ -313,36 +340,32 @@ impl Player {
             return;
         }
 
-        let random_room = ::rand::thread_rng().choose(&possible_rooms).unwrap();
+        let random_room = rng.choose(&possible_rooms).unwrap();
         self.player_mat.tiles[*random_room].build_room(self.house_type.clone());
         self.pay_for_room();
     }
 
-    pub fn build_stables(&mut self) {
-        let available_stables = 4 - self.stables;
-        let max_stables = ::std::cmp::min(available_stables, self.wood / 2);
-        if max_stables == 0 { return; }
-        let num_stables = ::rand::random::<usize>() % (max_stables) + 1; // Always guarentee at least one stable
-        for _ in 0..num_stables {
-            let possibles: Vec<usize> = self.player_mat.tiles
-                                                .iter()
-                                                .enumerate()
-                                                .filter(|&(i, t)| t.is_empty())
-                                                .map(|(i, t)| i)
-                                                .collect();
+    /// Empty farmyard tiles a new stable can go on, if the player has a stable and the wood for
+    /// it left
+    pub fn stable_options(&self) -> Vec<usize> {
+        if self.stables == 4 || self.wood < 2 {
+            return Vec::new();
+        }
 
-            if possibles.len() == 0 {
-                return;
-            }
+        self.player_mat.tiles.iter()
+                             .filter(|t| t.is_empty())
+                             .map(|t| t.index)
+                             .collect()
+    }
 
-            let random_tile = ::rand::thread_rng().choose(&possibles).unwrap();
-            self.player_mat.tiles[*random_tile].stable();
-            self.wood -= 2;
-            self.stables += 1;
-        }
+    /// Build a stable on the given tile for 2 wood
+    pub fn build_stable_on(&mut self, tile: usize) {
+        self.player_mat.tiles[tile].stable();
+        self.wood -= 2;
+        self.stables += 1;
     }
 
-    pub fn build_stable(&mut self) {
+    pub fn build_stable(&mut self, rng: &mut GameRng) {
         if self.wood == 0 || self.stables == 4{
             // Not enough wood to buy one stable or no available stables
             return;
@@ -359,7 +382,7 @@ impl Player {
             return;
         }
 
-        let random_tile = ::rand::thread_rng().choose(&possibles).unwrap();
+        let random_tile = rng.choose(&possibles).unwrap();
         self.player_mat.tiles[*random_tile].stable();
         self.wood -= 1;
         self.stables += 1;
@@ -385,6 +408,40 @@ impl Player {
         }
     }
 
+    /// Fields with nothing growing in them
+    fn empty_fields(&self) -> Vec<usize> {
+        self.player_mat.tiles.iter()
+                             .enumerate()
+                             .filter(|&(_, t)| t.field.as_ref().is_some_and(|f| f.count == 0))
+                             .map(|(i, _)| i)
+                             .collect()
+    }
+
+    /// There is an empty field and grain or vegetables to sow in it
+    pub fn can_sow(&self) -> bool {
+        (self.grains > 0 || self.vegetables > 0) && !self.empty_fields().is_empty()
+    }
+
+    /// Sow one grain or vegetable from the supply into the next empty field
+    pub fn sow_next_field(&mut self, crop: Crop) {
+        let field_index = match self.empty_fields().pop() {
+            Some(index) => index,
+            None => return
+        };
+
+        match crop {
+            Crop::Grain if self.grains > 0 => {
+                self.sow_grain(field_index);
+                self.grains -= 1;
+            },
+            Crop::Vegetable if self.vegetables > 0 => {
+                self.sow_veg(field_index);
+                self.vegetables -= 1;
+            },
+            _ => {}
+        }
+    }
+
     pub fn sow_veg(&mut self, index: usize) {
         self.player_mat.tiles[index].sow_veg();
     }
@@ -407,301 +464,158 @@ impl Player {
         }
     }
 
-    pub fn make_pastures(&mut self) -> usize {
-        let mut fences_built = 0;
-
-        for i in 0..20 {
-            let mut curr_pasture = HashSet::new();
-
-            {
-                let empty_spaces: Vec<&FarmTile> = self.player_mat.tiles.iter()
-                                                                        .filter(|&t| t.can_be_fenced())
-                                                                        .collect(); 
-                    
-
-                if let Some(temp_space) = ::rand::thread_rng().choose(&empty_spaces) {
-                    let mut curr_space = *temp_space;
-                    curr_pasture.insert(curr_space.index);
-                    loop {
-                        if ::rand::random::<usize>() % 100 < 20 {
-                            break;
-                        }
-                        
-                        let surrounding_tiles: Vec<&usize> = curr_space.surrounding_tiles.iter()
-                                                                                        .filter(|&t| self.player_mat.tiles[*t].can_be_fenced())
-                                                                                        .collect();
-
-                        if let Some(surrounding_tile) = ::rand::thread_rng().choose(&surrounding_tiles) {
-                            curr_space = &self.player_mat.tiles[**surrounding_tile];
-                            curr_pasture.insert(**surrounding_tile);
-                        } else {
-                            break;
-                        }
+    /// Pastures of any number of connected tiles that the fences and wood left can enclose, each
+    /// ordered by tile
+    pub fn pasture_options(&self) -> Vec<Vec<usize>> {
+        let max_fences = ::std::cmp::min(15-self.fences, self.wood);
+        let tiles = &self.player_mat.tiles;
+
+        // Grow every connected set of tiles one neighbour at a time, as a bit per tile
+        let mut pastures = HashSet::new();
+        let mut to_grow: Vec<u16> = tiles.iter()
+                                         .filter(|t| t.can_be_fenced())
+                                         .map(|t| 1 << t.index)
+                                         .collect();
+        while let Some(pasture) = to_grow.pop() {
+            if !pastures.insert(pasture) {
+                continue;
+            }
+            for tile in tiles.iter().filter(|t| pasture & (1 << t.index) != 0) {
+                for &neighbour in &tile.surrounding_tiles {
+                    let grown = pasture | (1 << neighbour);
+                    if grown != pasture && tiles[neighbour].can_be_fenced() && !pastures.contains(&grown) {
+                        to_grow.push(grown);
                     }
-                } else {
-                    // println!("[Make Pastures] No more empty spaces.. cannot make pasture");
                 }
             }
+        }
 
-            // println!("Fences: {} Wood: {}", self.fences, self.wood);
-            // println!("Max fences: min({}, {})", 15-self.fences, self.wood);
-            let max_fences = std::cmp::min(15-self.fences, self.wood);
-
-            if curr_pasture.len() > 0 {
-                if let Some(wood_used) = self.player_mat.make_pasture(curr_pasture.clone().into_iter().collect(), max_fences) {
-                    // if wood_used+self.fences > 15 {
-                        // println!("Max fences: min({}, {})", 15-self.fences, self.wood);
-                        // println!("Wood used: {}", wood_used);
-                    // }
-
-                    let stables = curr_pasture.iter().filter(|&&t| self.player_mat.tiles[t].stable == true).collect::<Vec<&usize>>().len();
-                    self.wood -= wood_used;
-                    self.fences += wood_used;
-                    self.pastures.push(
-                        Pasture::new(curr_pasture.into_iter().collect(), stables)
-                    );
-                    fences_built += wood_used;
-                }
-            }
+        let mut options: Vec<Vec<usize>> = pastures.into_iter()
+                                                   .map(|pasture| (0..15).filter(|i| pasture & (1 << i) != 0).collect())
+                                                   .filter(|pasture: &Vec<usize>| self.player_mat.pasture_cost(pasture) <= max_fences)
+                                                   .collect();
+        options.sort();
+        options
+    }
+
+    /// Fence the given tiles into a new pasture, returns the number of fences built
+    pub fn fence_pasture(&mut self, curr_pasture: Vec<usize>) -> usize {
+        let max_fences = ::std::cmp::min(15-self.fences, self.wood);
+        match self.player_mat.make_pasture(curr_pasture.clone(), max_fences) {
+            Some(wood_used) => {
+                let stables = curr_pasture.iter().filter(|&&t| self.player_mat.tiles[t].stable).count();
+                self.wood -= wood_used;
+                self.fences += wood_used;
+                self.pastures.push(Pasture::new(curr_pasture, stables));
+                wood_used
+            },
+            None => 0
         }
+    }
 
-        fences_built
+    /// Take every animal off the farm to be housed again, returns the sheep, boar and cattle
+    pub fn unhouse_animals(&mut self) -> (usize, usize, usize) {
+        self.pet = None;
+        for tile in self.player_mat.tiles.iter_mut() {
+            tile.animal_type = None;
+            tile.animal_count = 0;
+        }
+
+        let animals = (self.sheep, self.boar, self.cattle);
+        self.sheep = 0;
+        self.boar = 0;
+        self.cattle = 0;
+        animals
     }
 
-    pub fn place_animals(&mut self) {
-        let orig_state = self.clone();
-        let mut best_score = -999;
-        let mut best_state = orig_state;
-
-        // TODO: We try 10 times to find the best animal placement.. more/less?
-        for i in 0..20 {
-            // println!("i: {}", i);
-            let mut temp_state = self.clone();
-
-            temp_state.animal_reset();
-            let mut animals_to_place = Vec::new();
-            let mut sheep_count = temp_state.sheep;
-            let mut boar_count = temp_state.boar;
-            let mut cattle_count = temp_state.cattle;
-
-            if sheep_count > 0 { animals_to_place.push("sheep"); }
-            if boar_count > 0 { animals_to_place.push("boar"); }
-            if cattle_count > 0 { animals_to_place.push("cattle"); }
-
-            temp_state.sheep = 0;
-            temp_state.boar = 0;
-            temp_state.cattle = 0;
-
-            // println!("[{:?}] -- Placing {} sheep, {} boar, {} cattle", animals_to_place, sheep_count, boar_count, cattle_count);
-
-            while animals_to_place.len() > 0 {
-                let available_pastures: Vec<&Pasture> = temp_state.pastures.iter()
-                                                                           .filter(|p| {
-                                                                                // Check if one of the tiles in the pasture is occupied
-                                                                                temp_state.player_mat.tiles[p.tiles[0]].animal_type.is_none()
-                                                                           }).collect();
-                if let Some(ref mut pasture) = rand::thread_rng().choose(&available_pastures) {
-                    let mut index = None;
-                    if let Some(random_animal) = rand::thread_rng().choose(&animals_to_place) {
-                        match random_animal {
-                            &"sheep" => {
-                                for tile_index in &pasture.tiles {
-                                    if temp_state.player_mat.tiles[*tile_index].animal_count > 0 { continue; }
-                                    if sheep_count >= pasture.capacity {
-                                        // println!("[{}] {:?}", pasture.capacity, tile_index);
-                                        let mut curr_tile = &mut temp_state.player_mat.tiles[*tile_index];
-                                        curr_tile.animal_type = Some(Animal::Sheep);
-                                        curr_tile.animal_count = pasture.capacity;
-                                        sheep_count -= pasture.capacity;
-                                        temp_state.sheep += pasture.capacity;
-                                        if sheep_count == 0 {
-                                            index = animals_to_place.iter().position(|x| *x == "sheep");
-                                        }
-                                    } else if sheep_count >= 0 {
-                                        let mut curr_tile = &mut temp_state.player_mat.tiles[*tile_index];
-                                        curr_tile.animal_type = Some(Animal::Sheep);
-                                        curr_tile.animal_count = sheep_count;
-                                        temp_state.sheep += sheep_count;
-                                        sheep_count = 0;
-                                        index = animals_to_place.iter().position(|x| *x == "sheep");
-                                    }
-                                }
-                            },
-                            &"boar" => {
-                                for tile_index in &pasture.tiles {
-                                    if boar_count >= pasture.capacity {
-                                        let mut curr_tile = &mut temp_state.player_mat.tiles[*tile_index];
-                                        curr_tile.animal_type = Some(Animal::Boar);
-                                        curr_tile.animal_count = pasture.capacity;
-                                        boar_count -= pasture.capacity;
-                                        temp_state.boar += pasture.capacity;
-                                        if boar_count == 0 {
-                                            index = animals_to_place.iter().position(|x| *x == "boar");
-                                        }
-                                    } else if boar_count >= 0 {
-                                        let mut curr_tile = &mut temp_state.player_mat.tiles[*tile_index];
-                                        curr_tile.animal_type = Some(Animal::Boar);
-                                        curr_tile.animal_count = boar_count;
-                                        temp_state.boar += boar_count;
-                                        boar_count = 0;
-                                        index = animals_to_place.iter().position(|x| *x == "boar");
-                                    }
-                                }
-                            },
-                            &"cattle" => {
-                                for tile_index in &pasture.tiles {
-                                    if cattle_count >= pasture.capacity {
-                                        let mut curr_tile = &mut temp_state.player_mat.tiles[*tile_index];
-                                        curr_tile.animal_type = Some(Animal::Cattle);
-                                        curr_tile.animal_count = pasture.capacity;
-                                        cattle_count -= pasture.capacity;
-                                        temp_state.cattle += pasture.capacity;
-                                        if cattle_count == 0 {
-                                            index = animals_to_place.iter().position(|x| *x == "cattle");
-                                        }
-                                    } else if cattle_count >= 0 {
-                                        let mut curr_tile = &mut temp_state.player_mat.tiles[*tile_index];
-                                        curr_tile.animal_type = Some(Animal::Cattle);
-                                        curr_tile.animal_count = cattle_count;
-                                        temp_state.cattle += cattle_count;
-                                        cattle_count = 0;
-                                        index = animals_to_place.iter().position(|x| *x == "cattle");
-                                    }
-                                }
-                            },
-                            _ => {}
-                        }
-                    }
-                    if let Some(i) = index { animals_to_place.remove(i); }
-                }
-                // println!("[{:?}] -- No pastures.. trying single stables..", animals_to_place);
-                for single_stable in temp_state.player_mat.tiles.iter_mut().filter(|t| t.stable == true && t.pasture == false && t.animal_type.is_none()) {
-                    let mut index = None;
-                    if let Some(random_animal) = rand::thread_rng().choose(&animals_to_place) {
-                        match random_animal {
-                            &"sheep" => { 
-                                single_stable.animal_type = Some(Animal::Sheep); 
-                                single_stable.animal_count = 1; 
-                                sheep_count -= 1; 
-                                temp_state.sheep += 1;
-                                if sheep_count == 0 {
-                                    index = animals_to_place.iter().position(|x| *x == "sheep");
-                                }
-                            },
-                            &"boar" => {
-                                single_stable.animal_type = Some(Animal::Boar); 
-                                single_stable.animal_count = 1; 
-                                boar_count -= 1; 
-                                temp_state.boar += 1;
-                                if boar_count == 0 {
-                                    index = animals_to_place.iter().position(|x| *x == "boar");
-                                }
-                            },
-                            &"cattle" => {
-                                single_stable.animal_type = Some(Animal::Cattle); 
-                                single_stable.animal_count = 1; 
-                                cattle_count -= 1; 
-                                temp_state.cattle += 1;
-                                if cattle_count == 0 {
-                                    index = animals_to_place.iter().position(|x| *x == "cattle");
-                                }
-                            },
-                            _ => {}
-                        }
-                    }
-                    if let Some(i) = index { 
-                        animals_to_place.remove(i); 
-                    }
-                }
+    /// Housing with no animals in it yet
+    pub fn housing_options(&self) -> Vec<Housing> {
+        let tiles = &self.player_mat.tiles;
+        let mut options: Vec<Housing> = self.pastures.iter()
+                                                     .enumerate()
+                                                     .filter(|&(_, p)| tiles[p.tiles[0]].animal_type.is_none())
+                                                     .map(|(i, _)| Housing::Pasture(i))
+                                                     .collect();
+        options.extend(tiles.iter()
+                            .filter(|t| t.stable && !t.pasture && t.animal_type.is_none())
+                            .map(|t| Housing::Stable(t.index)));
+        if self.pet.is_none() {
+            options.push(Housing::House);
+        }
+        options
+    }
 
-                let mut index = None;
-                if temp_state.pet.is_none() && animals_to_place.len() > 0 {
-                    // println!("[{:?}] -- No pastures left, no single stables, trying pet..", animals_to_place);
-                    if let Some(random_animal) = rand::thread_rng().choose(&animals_to_place) {
-                        match random_animal {
-                            &"sheep" => { 
-                                temp_state.pet = Some(Animal::Sheep); 
-                                temp_state.player_mat.tiles[10].animal_type = Some(Animal::Sheep);  
-                                temp_state.player_mat.tiles[10].animal_count = 1;  
-                                temp_state.sheep += 1;
-                                sheep_count -= 1;
-                                if sheep_count == 0 {
-                                    index = animals_to_place.iter().position(|x| *x == "sheep");
-                                }
-                            },
-                            &"boar" => {
-                                temp_state.pet = Some(Animal::Boar); 
-                                temp_state.player_mat.tiles[10].animal_type = Some(Animal::Boar);  
-                                temp_state.player_mat.tiles[10].animal_count = 1;  
-                                temp_state.boar += 1;
-                                boar_count -=1 ;
-                                if boar_count == 0 {
-                                    index = animals_to_place.iter().position(|x| *x == "boar");
-                                }
-                            },
-                            &"cattle" => {
-                                temp_state.pet = Some(Animal::Cattle); 
-                                temp_state.player_mat.tiles[10].animal_type = Some(Animal::Cattle);  
-                                temp_state.player_mat.tiles[10].animal_count = 1;  
-                                temp_state.cattle += 1;
-                                cattle_count -= 1;
-                                if cattle_count == 0 {
-                                    index = animals_to_place.iter().position(|x| *x == "cattle");
-                                }
-                            },
-                            _ => {}
-                        }
-                    }
-                }
-                if let Some(i) = index { 
-                    animals_to_place.remove(i); 
-                }
+    /// Number of animals `housing` holds
+    pub fn housing_capacity(&self, housing: &Housing) -> usize {
+        match *housing {
+            Housing::Pasture(index) => self.pastures[index].capacity * self.pastures[index].tiles.len(),
+            Housing::Stable(_) | Housing::House => 1
+        }
+    }
 
-                break; // nothing else is available.. break
+    /// Put up to `count` of `animal` in the empty `housing`, returns how many fit
+    pub fn house_animals(&mut self, housing: &Housing, animal: Animal, count: usize) -> usize {
+        let (tiles, per_tile) = match *housing {
+            Housing::Pasture(index) => (self.pastures[index].tiles.clone(), self.pastures[index].capacity),
+            Housing::Stable(tile) => (vec!(tile), 1),
+            Housing::House => {
+                self.pet = Some(animal.clone());
+                (vec!(10), 1)
             }
+        };
 
-            if self.improvements.contains(&MajorImprovement::CookingHearth_4) || self.improvements.contains(&MajorImprovement::CookingHearth_5) && 
-                (sheep_count > 0 || boar_count > 0 || cattle_count > 0) {
-                // println!("Killing animals in cooking hearth: sheep {} ({}) boar {} ({}) cattle {} ({})", sheep_count, 2*sheep_count, boar_count, 2*boar_count, cattle_count, 3*cattle_count);
-                temp_state.food += 2 * sheep_count;
-                temp_state.food += 3 * boar_count;
-                temp_state.food += 4 * cattle_count;
-            } else if self.improvements.contains(&MajorImprovement::Fireplace_2) || self.improvements.contains(&MajorImprovement::Fireplace_3) && 
-                (sheep_count > 0 || boar_count > 0 || cattle_count > 0) {
-                // println!("Killing animals in fireplace: sheep {} ({}) boar {} ({}) cattle {} ({})", sheep_count, 2*sheep_count, boar_count, 2*boar_count, cattle_count, 3*cattle_count);
-                temp_state.food += 2 * sheep_count;
-                temp_state.food += 2 * boar_count;
-                temp_state.food += 3 * cattle_count;
-            }
+        let mut housed = 0;
+        for tile_index in tiles {
+            let tile = &mut self.player_mat.tiles[tile_index];
+            tile.animal_type = Some(animal.clone());
+            tile.animal_count = ::std::cmp::min(per_tile, count - housed);
+            housed += tile.animal_count;
+        }
 
-            if temp_state.score(false) > best_score {
-                best_score = temp_state.score(false);
-                best_state = temp_state;
-            }
+        match animal {
+            Animal::Sheep => self.sheep += housed,
+            Animal::Boar => self.boar += housed,
+            Animal::Cattle => self.cattle += housed
         }
+        housed
+    }
 
-        self.sheep = best_state.sheep;
-        self.boar = best_state.boar;
-        self.cattle = best_state.cattle;
-        self.player_mat = best_state.player_mat;
-        self.food = best_state.food;
+    /// Cook the animals there is no room for if the player has a cooking improvement, otherwise
+    /// they run off. Returns the food gained.
+    pub fn turn_away_animals(&mut self, sheep: usize, boar: usize, cattle: usize) -> usize {
+        let food = if self.improvements.contains(&MajorImprovement::CookingHearth_4) ||
+                      self.improvements.contains(&MajorImprovement::CookingHearth_5) {
+            2 * sheep + 3 * boar + 4 * cattle
+        } else if self.improvements.contains(&MajorImprovement::Fireplace_2) ||
+                  self.improvements.contains(&MajorImprovement::Fireplace_3) {
+            2 * sheep + 2 * boar + 3 * cattle
+        } else {
+            0
+        };
+        self.food += food;
+        food
     }
 
-    fn animal_reset(&mut self) {
-        self.pet = None;
-        for pasture in &self.pastures {
-            for tile_index in &pasture.tiles {
-                self.player_mat.tiles[*tile_index].animal_type = None;
-                self.player_mat.tiles[*tile_index].animal_count = 0;
+    /// House every animal again, the largest herd in the roomiest empty housing first, and turn
+    /// away the ones left. Returns the food gained.
+    pub fn rehouse_animals(&mut self) -> usize {
+        let (sheep, boar, cattle) = self.unhouse_animals();
+        let mut herds = vec!((Animal::Sheep, sheep), (Animal::Boar, boar), (Animal::Cattle, cattle));
+        loop {
+            herds.sort_by_key(|&(_, count)| ::std::cmp::Reverse(count));
+            let housing = self.housing_options().into_iter().max_by_key(|h| self.housing_capacity(h));
+            match housing {
+                Some(ref housing) if herds[0].1 > 0 => {
+                    let housed = self.house_animals(housing, herds[0].0.clone(), herds[0].1);
+                    herds[0].1 -= housed;
+                },
+                _ => break
             }
         }
 
-        for single_stable in self.player_mat.tiles.iter_mut().filter(|t| t.stable == true && t.pasture == false) {
-            single_stable.animal_type = None;
-        }
-
-        self.player_mat.tiles[10].animal_type = None;
-        self.player_mat.tiles[10].animal_count = 0;
+        let left = |animal: Animal| herds.iter().find(|h| h.0 == animal).map_or(0, |h| h.1);
+        let (sheep, boar, cattle) = (left(Animal::Sheep), left(Animal::Boar), left(Animal::Cattle));
+        self.turn_away_animals(sheep, boar, cattle)
     }
 
     pub fn bake_bread(&mut self) -> usize {
@@ -733,6 +647,30 @@ impl Player {
     }
 }
 
+impl Player {
+    /// Append the player's supplies: food, fields, grains, vegetables, wood, clay, reed, stone,
+    /// sheep, cattle, boar, actions left, total actions, beggars, children, stables, fences and
+    /// pastures, then the house (wood, clay, stone flags), the pet (sheep, boar, cattle flags), a
+    /// flag for each major improvement in declaration order, and every farm tile by index
+    pub fn encode(&self, features: &mut Vec<f32>) {
+        let supplies = [self.food, self.fields, self.grains, self.vegetables, self.wood, self.clay,
+                        self.reed, self.stone, self.sheep, self.cattle, self.boar, self.actions,
+                        self.total_actions, self.beggers, self.children, self.stables, self.fences,
+                        self.pastures.len()];
+        features.extend(supplies.iter().map(|&supply| supply as f32));
+        one_hot(Some(self.house_type.clone() as usize), 3, features);
+        one_hot(self.pet.clone().map(|pet| pet as usize), 3, features);
+        let mut improvements = [0.0; 10];
+        for improvement in &self.improvements {
+            improvements[improvement.clone() as usize] = 1.0;
+        }
+        features.extend_from_slice(&improvements);
+        for tile in &self.player_mat.tiles {
+            tile.encode(features);
+        }
+    }
+}
+
 impl Display for Player {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Score: {}\n", self.score(false));
@@ -42,6 +42,12 @@ impl PlayerMat {
         PlayerMat { tiles: player_mat }
     }
 
+    pub fn stable_hash(&self, hasher: &mut StableHasher) {
+        for tile in &self.tiles {
+            tile.stable_hash(hasher);
+        }
+    }
+
     /*
     /// Given a number, place a fence at that location for both tiles touching that fence location
     /// Returns: True, placed fence; False, already occupied
@@ -225,7 +231,7 @@ impl PlayerMat {
     }
     */
 
-    pub fn plow_random_field(&mut self) {
+    pub fn plow_random_field(&mut self, rng: &mut GameRng) {
         // Get vector of indexes of current fields in the player mat
         let curr_fields: Vec<usize> = self.tiles
                                           .iter()
@@ -243,7 +249,7 @@ impl PlayerMat {
         if possible_fields.len() == 0 {
             return;
         }
-        let random_field = ::rand::thread_rng().choose(&possible_fields).unwrap();
+        let random_field = rng.choose(&possible_fields).unwrap();
         self.tiles[*random_field].plow();
     }
 
@@ -294,44 +300,34 @@ impl PlayerMat {
     }
     */
 
-    pub fn make_pasture(&mut self, curr_pasture: Vec<usize>, available_wood: usize) -> Option<usize> {
-        let test_pasture = curr_pasture.clone();
-
-        // Calculate how much wood is necessary to build the pasture before actually setting it
+    /// Wood needed to fence `curr_pasture`, reusing the fences already on the farm
+    pub fn pasture_cost(&self, curr_pasture: &[usize]) -> usize {
         let mut wood_needed = 0;
-        for tile_index in &test_pasture {
-            if wood_needed > available_wood {
-                // println!("Not enough wood for pasture..");
-                return None;
-            }
-            {
-                let curr_tile = &mut self.tiles[*tile_index];
-                if !curr_tile.can_be_fenced() {
-                    panic!("Make pasture tile is currently occupied");
-                    return None;
-                }
+        for tile_index in curr_pasture {
+            if !self.tiles[*tile_index].can_be_fenced() {
+                panic!("Make pasture tile is currently occupied");
             }
 
             match tile_index {
                 &4|&9|&14 => { 
-                    if !self.tiles[*tile_index].west_fence {
+                    if !self.tiles[*tile_index].east_fence {
                         wood_needed += 1; 
                     }
                 },
                 _ => { 
-                    if !curr_pasture.contains(&(tile_index+1)) && !self.tiles[*tile_index].west_fence {
+                    if !curr_pasture.contains(&(tile_index+1)) && !self.tiles[*tile_index].east_fence {
                         wood_needed += 1;
                     }
                 }
             }
             match tile_index {
                 &0|&5|&10 => { 
-                    if !self.tiles[*tile_index].east_fence {
+                    if !self.tiles[*tile_index].west_fence {
                         wood_needed += 1; 
                     }
                 },
                 _ => { 
-                    if !curr_pasture.contains(&(tile_index-1)) && !self.tiles[*tile_index].east_fence {
+                    if !curr_pasture.contains(&(tile_index-1)) && !self.tiles[*tile_index].west_fence {
                         wood_needed += 1;
                     }
                 }
@@ -362,6 +358,14 @@ impl PlayerMat {
             }
         }
 
+        wood_needed
+    }
+
+    pub fn make_pasture(&mut self, curr_pasture: Vec<usize>, available_wood: usize) -> Option<usize> {
+        let test_pasture = curr_pasture.clone();
+
+        // Calculate how much wood is necessary to build the pasture before actually setting it
+        let wood_needed = self.pasture_cost(&curr_pasture);
         if wood_needed > available_wood {
             // println!("Not enough wood for pasture..");
             return None;
@@ -0,0 +1,74 @@
+extern crate agricola;
+extern crate boardgameai_rs;
+extern crate rand;
+use agricola::*;
+use boardgameai_rs::*;
+use boardgameai_rs::state::{FeatureEncode, State, StateHash};
+use rand::Rng;
+
+/// Index of the first action space's features for `players` players
+fn board_start(players: usize) -> usize {
+    10 + 2 * players
+}
+
+/// Index of the first of `player`'s features
+fn player_start(players: usize, player: usize) -> usize {
+    board_start(players) + BOARD_TILES * (2 + players) + player * PLAYER_FEATURES
+}
+
+#[test]
+fn features_follow_the_documented_layout() {
+    for players in 2..5 {
+        let mut rng = seeded_rng(players as u64);
+        let mut state = AgricolaState::new(players, &mut rng);
+        let features = state.features();
+        assert_eq!(features.len(), state.feature_len());
+        assert_eq!(features[0], 1.0);
+
+        // Wood is on the board from the start with 3 wood on it, and nobody on it yet
+        let wood = board_start(players) + (AgricolaTile::Wood as usize - 1) * (2 + players);
+        assert_eq!(&features[wood..wood + 2], &[1.0, 3.0]);
+        assert!(features[wood + 2..wood + 2 + players].iter().all(|&flag| flag == 0.0));
+
+        let player = state.current_player;
+        state.do_action(AgricolaAction::Wood, &mut rng);
+        let features = state.features();
+        assert_eq!(features[wood + 1], 0.0);
+        assert_eq!(features[wood + 2 + player], 1.0);
+        // Wood is the fifth of the player's supplies
+        assert_eq!(features[player_start(players, player) + 4], 3.0);
+    }
+}
+
+#[test]
+fn features_keep_their_length_for_a_whole_game() {
+    for players in 2..5 {
+        let mut rng = seeded_rng(10 + players as u64);
+        let mut state = AgricolaState::new(players, &mut rng);
+        loop {
+            let features = state.features();
+            assert_eq!(features.len(), state.feature_len());
+            assert_eq!(features[0], state.rounds as f32);
+            for (player, supplies) in state.players.iter().enumerate() {
+                assert_eq!(features[player_start(players, player)], supplies.food as f32);
+            }
+
+            if let Some(card) = state.sample_chance_outcome(&mut rng) {
+                state.do_action(card, &mut rng);
+                continue;
+            }
+            let actions = state.get_actions();
+            match rng.choose(&actions) {
+                Some(action) => state.do_action(action.clone(), &mut rng),
+                None => break,
+            }
+        }
+    }
+}
+
+#[test]
+fn state_hash_is_the_same_on_every_platform_and_release() {
+    // Opening books saved to disk are keyed by this hash, so changing it orphans every saved book
+    let state = AgricolaState::new(2, &mut seeded_rng(0));
+    assert_eq!(state.state_hash(), 6_039_778_289_478_338_062);
+}
@@ -1,6 +1,6 @@
 extern crate boardgameai_rs;
 use boardgameai_rs::*;
-use boardgameai_rs::state::State;
+use boardgameai_rs::state::{FeatureEncode, State, StateHash};
 use boardgameai_rs::action::Action;
 
 #[derive(Debug, Clone, Copy)]
@@ -9,71 +9,71 @@ pub struct NimState {
     chips: u32
 }
 
-#[derive(Debug)]
-enum NimAction {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NimAction {
     One = 1,
     Two = 2,
     Three = 3
 }
 
-impl NimAction {
-    fn from_u32(x: u32) -> Option<NimAction> {
-        match x {
-            1 => Some(NimAction::One),
-            2 => Some(NimAction::Two),
-            3 => Some(NimAction::Three),
-            _ => None
-        }
-    }
-}
-
 impl State for NimState {
+    type Action = NimAction;
+
     fn get_player_just_moved(&self) -> usize {
         self.player_just_moved
     }
 
-    fn get_actions(&self) -> Vec<u32> {
+    fn get_actions(&self) -> Vec<NimAction> {
         let mut actions = Vec::new();
         if self.chips >= 1 {
-            actions.push(NimAction::One as u32);
+            actions.push(NimAction::One);
         } 
         if self.chips >= 2 {
-            actions.push(NimAction::Two as u32);
+            actions.push(NimAction::Two);
         } 
         if self.chips >= 3 {
-            actions.push(NimAction::Three as u32);
+            actions.push(NimAction::Three);
         } 
         actions
     }
 
-    fn get_action_strings(&self) -> Vec<String> {
-        let mut strings = Vec::new();
-        for action in self.get_actions() {
-            strings.push(format!("{:?}", NimAction::from_u32(action).unwrap()));
-        }
-        strings
+    fn do_action(&mut self, action: NimAction, _rng: &mut GameRng) {
+        self.chips -= action as u32;
+        // Player 0: 1 - 0 -> 1
+        // Player 1: 1 - 1 -> 0
+        self.player_just_moved = 1 - self.player_just_moved
     }
 
-    fn do_action(&mut self, action: u32) {
-        self.chips -= action;
-        // Player 1: 3 - 1 -> 2
-        // Player 2: 3 - 2 -> 1
-        self.player_just_moved = 3 - self.player_just_moved
+    fn get_rewards(&self) -> Vec<f32> {
+        // Whoever took the last chip wins
+        let mut rewards = vec!(0.0, 0.0);
+        rewards[self.player_just_moved] = 1.0;
+        rewards
     }
+}
 
-    fn get_result(&self, player: usize) -> f32 {
-        if self.player_just_moved == player {
-            return 1.0;
-        } else {
-            return 0.0;
-        }
+impl StateHash for NimState {
+    fn state_hash(&self) -> u64 {
+        // Taking 1 then 2 leaves the same game as taking 2 then 1
+        (self.chips as u64) << 1 | self.player_just_moved as u64
+    }
+}
+
+/// Layout: `[chips left, player who just moved]`
+impl FeatureEncode for NimState {
+    fn feature_len(&self) -> usize {
+        2
+    }
+
+    fn features(&self) -> Vec<f32> {
+        vec!(self.chips as f32, self.player_just_moved as f32)
     }
 }
 
 impl NimState {
     pub fn new(chips: u32) -> NimState {
         NimState {
-            player_just_moved: 2,
+            player_just_moved: 1,
             chips: chips
         }
     }
@@ -0,0 +1,15 @@
+extern crate boardgameai_rs;
+extern crate nim;
+use boardgameai_rs::*;
+use boardgameai_rs::state::{FeatureEncode, State};
+use nim::*;
+
+#[test]
+fn features_are_chips_and_last_mover() {
+    let mut state = NimState::new(7);
+    assert_eq!(state.features(), vec!(7.0, 1.0));
+    assert_eq!(state.features().len(), state.feature_len());
+
+    state.do_action(NimAction::Two, &mut seeded_rng(0));
+    assert_eq!(state.features(), vec!(5.0, 0.0));
+}
@@ -1,3 +1,8 @@
-pub trait Action {
+use std::fmt::Debug;
+use std::hash::Hash;
 
-}
+/// Everything the search needs from a game's actions. Implemented for every type with these
+/// bounds, so a game can use its own action enum directly.
+pub trait Action: Clone + Eq + Hash + Debug + Send + Sync {}
+
+impl<T: Clone + Eq + Hash + Debug + Send + Sync> Action for T {}
@@ -0,0 +1,120 @@
+use action::Action;
+use result::{ChildStats, SearchResult};
+use rng::GameRng;
+use save::{read_versioned, SaveError};
+use search::Mcts;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+use state::StateHash;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+/// Version of the file format written by `OpeningBook::save`
+pub const BOOK_FORMAT_VERSION: u64 = 1;
+
+/// Moves found by long searches, ahead of time, for positions that come up in many games. A
+/// searcher given the book with `Mcts::opening_book` plays them without searching.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpeningBook<A> {
+    /// Statistics of the chosen move, by the `StateHash` of the position it was chosen in
+    pub moves: HashMap<u64, ChildStats<A>>,
+}
+
+impl<A> Default for OpeningBook<A> {
+    fn default() -> OpeningBook<A> {
+        OpeningBook::new()
+    }
+}
+
+impl<A> OpeningBook<A> {
+    pub fn new() -> OpeningBook<A> {
+        OpeningBook { moves: HashMap::new() }
+    }
+
+    /// Number of positions in the book
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+/// Layout of a book file as written
+#[derive(Serialize)]
+struct BookFileRef<'a, A: 'a> {
+    version: u64,
+    moves: &'a HashMap<u64, ChildStats<A>>,
+}
+
+/// Layout of a book file as read back
+#[derive(Deserialize)]
+struct BookFile<A> {
+    moves: HashMap<u64, ChildStats<A>>,
+}
+
+impl<A: Action> OpeningBook<A> {
+    /// The book's move for `state`, if the position is in the book
+    pub fn get<S: StateHash<Action = A>>(&self, state: &S) -> Option<&ChildStats<A>> {
+        self.moves.get(&state.state_hash())
+    }
+
+    /// Record the action a search of `state` chose, along with its statistics
+    pub fn insert<S: StateHash<Action = A>>(&mut self, state: &S, result: &SearchResult<A>) {
+        if let Some(child) = result.children.iter().find(|child| child.action == result.action) {
+            self.moves.insert(state.state_hash(), child.clone());
+        }
+    }
+
+    /// Search every position up to `depth` moves below `rootstate` that follows one of the
+    /// `breadth` most visited moves of the position before it, and record the chosen moves. Every
+    /// outcome of a chance event is followed without counting as a move. `mcts` should be
+    /// searching with a generous budget and without an opening book of its own.
+    pub fn fill<S>(&mut self, mcts: &mut Mcts<S>, rootstate: &S, depth: usize, breadth: usize, rng: &mut GameRng)
+        where S: StateHash<Action = A> + Clone + Send {
+        let outcomes = rootstate.get_chance_outcomes();
+        if !outcomes.is_empty() {
+            for (outcome, _) in outcomes {
+                let mut state = rootstate.clone();
+                state.do_action(outcome, rng);
+                self.fill(mcts, &state, depth, breadth, rng);
+            }
+            return;
+        }
+
+        if depth == 0 || self.moves.contains_key(&rootstate.state_hash()) {
+            return;
+        }
+
+        let result = match mcts.analyze(rootstate) {
+            Ok(result) => result,
+            Err(_) => return,
+        };
+        self.insert(rootstate, &result);
+
+        for child in result.children.iter().take(breadth) {
+            let mut state = rootstate.clone();
+            state.do_action(child.action.clone(), rng);
+            self.fill(mcts, &state, depth - 1, breadth, rng);
+        }
+    }
+
+    /// Write the book as versioned JSON
+    pub fn save<W: Write>(&self, writer: W) -> Result<(), SaveError> where A: Serialize {
+        let file = BookFileRef {
+            version: BOOK_FORMAT_VERSION,
+            moves: &self.moves,
+        };
+        serde_json::to_writer(writer, &file)?;
+        Ok(())
+    }
+
+    /// Read a book written by `save`
+    pub fn load<R: Read>(reader: R) -> Result<OpeningBook<A>, SaveError> where A: DeserializeOwned {
+        let value = read_versioned(reader, BOOK_FORMAT_VERSION)?;
+        let file: BookFile<A> = serde_json::from_value(value)?;
+        Ok(OpeningBook { moves: file.moves })
+    }
+}
@@ -0,0 +1,80 @@
+use state::State;
+
+/// What an evaluator predicts about one state
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// Prior probability of each action, in the order `State::get_actions` returns them. Empty
+    /// when the state has no actions.
+    pub priors: Vec<f32>,
+    /// Expected result of every player from 0.0 - 1.0, like `State::get_rewards`
+    pub values: Vec<f32>,
+}
+
+/// A model that scores positions in place of rollouts, such as a policy/value network. The
+/// search hands it the leaves of several iterations at once, so models that run faster on batches
+/// can evaluate them together.
+pub trait Evaluator<S: State>: Send + Sync {
+    /// One evaluation per state, in the same order
+    fn evaluate(&self, states: &[S]) -> Vec<Evaluation>;
+}
+
+/// Extracts a fixed-length vector of numbers from a state
+pub type StateFeatures<S> = dyn Fn(&S) -> Vec<f32> + Send + Sync;
+
+/// Extracts a fixed-length vector of numbers from playing an action in a state
+pub type ActionFeatures<S> = dyn Fn(&S, &<S as State>::Action) -> Vec<f32> + Send + Sync;
+
+/// A linear model that runs on the CPU: each player's value is a logistic function of the state
+/// features, and the priors are a softmax over a linear function of each action's features.
+/// All weights zero predicts uniform priors and even values.
+pub struct LinearEvaluator<S: State> {
+    /// Weights of the state features for each player's value
+    pub value_weights: Vec<Vec<f32>>,
+    /// Bias of each player's value
+    pub value_bias: Vec<f32>,
+    /// Weights of the action features for the priors
+    pub policy_weights: Vec<f32>,
+    state_features: Box<StateFeatures<S>>,
+    action_features: Box<ActionFeatures<S>>,
+}
+
+impl<S: State> LinearEvaluator<S> {
+    /// A model for `players` players with every weight zero
+    pub fn new<F, G>(players: usize, state_features: F, value_features: usize, action_features: G, policy_features: usize) -> LinearEvaluator<S>
+        where F: Fn(&S) -> Vec<f32> + Send + Sync + 'static, G: Fn(&S, &S::Action) -> Vec<f32> + Send + Sync + 'static {
+        LinearEvaluator {
+            value_weights: vec!(vec!(0.0; value_features); players),
+            value_bias: vec!(0.0; players),
+            policy_weights: vec!(0.0; policy_features),
+            state_features: Box::new(state_features),
+            action_features: Box::new(action_features),
+        }
+    }
+
+    fn evaluate_one(&self, state: &S) -> Evaluation {
+        let features = (self.state_features)(state);
+        let values = self.value_weights.iter().zip(&self.value_bias)
+            .map(|(weights, bias)| 1.0 / (1.0 + (-(dot(weights, &features) + bias)).exp()))
+            .collect();
+
+        let logits: Vec<f32> = state.get_actions().iter()
+            .map(|action| dot(&self.policy_weights, &(self.action_features)(state, action)))
+            .collect();
+        let max_logit = logits.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let exps: Vec<f32> = logits.iter().map(|logit| (logit - max_logit).exp()).collect();
+        let total: f32 = exps.iter().sum();
+        let priors = exps.iter().map(|exp| exp / total).collect();
+
+        Evaluation { priors, values }
+    }
+}
+
+impl<S: State> Evaluator<S> for LinearEvaluator<S> {
+    fn evaluate(&self, states: &[S]) -> Vec<Evaluation> {
+        states.iter().map(|state| self.evaluate_one(state)).collect()
+    }
+}
+
+fn dot(weights: &[f32], features: &[f32]) -> f32 {
+    weights.iter().zip(features).map(|(weight, feature)| weight * feature).sum()
+}
@@ -0,0 +1,105 @@
+use action::Action;
+use node::{NodeArena, NodeId};
+use serde_json;
+use std::io::{self, Write};
+
+/// Which nodes of a tree an export includes. The root is always included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExportFilter {
+    /// Leave out nodes visited fewer times than this, along with everything below them
+    pub min_visits: u32,
+    /// Leave out nodes more than this many moves below the root
+    pub max_depth: Option<usize>,
+}
+
+impl ExportFilter {
+    pub fn new(min_visits: u32, max_depth: Option<usize>) -> ExportFilter {
+        ExportFilter { min_visits, max_depth }
+    }
+
+    /// Returns true if a node with `visits` visits, `depth` moves below the root, is exported
+    fn includes(&self, visits: u32, depth: usize) -> bool {
+        visits >= self.min_visits && self.max_depth.map_or(true, |max_depth| depth <= max_depth)
+    }
+}
+
+impl<A: Action> NodeArena<A> {
+    /// Write the tree below `root` as a Graphviz DOT graph, one box per node showing its action,
+    /// wins/visits and the player who moved into it
+    pub fn write_dot<W: Write>(&self, root: NodeId, filter: ExportFilter, out: &mut W) -> io::Result<()> {
+        writeln!(out, "digraph tree {{")?;
+        writeln!(out, "    node [shape=box];")?;
+
+        // Nodes are numbered in the order they are written
+        let mut stack = vec!((root, 0, None));
+        let mut next_name = 0;
+        while let Some((node_id, depth, parent_name)) = stack.pop() {
+            let node = &self[node_id];
+            let name = next_name;
+            next_name += 1;
+
+            let action = node.action.as_ref().map_or(String::from("Root"), |action| format!("{:?}", action));
+            writeln!(out, "    n{} [label=\"{}\\nW/V: {}/{}\\nPlayer {}\"];",
+                name, escape(&action), node.wins, node.visits, node.player_just_moved)?;
+            if let Some(parent_name) = parent_name {
+                writeln!(out, "    n{} -> n{};", parent_name, name)?;
+            }
+
+            for child in node.children.iter().rev() {
+                if filter.includes(self[*child].visits, depth + 1) {
+                    stack.push((*child, depth + 1, Some(name)));
+                }
+            }
+        }
+
+        writeln!(out, "}}")
+    }
+
+    /// Write the tree below `root` as nested JSON objects with the action (as its `Debug`
+    /// string, null for the root), wins, visits, player_just_moved and children of each node.
+    /// Wins that are not a finite number are written as null.
+    pub fn write_json<W: Write>(&self, root: NodeId, filter: ExportFilter, out: &mut W) -> io::Result<()> {
+        serde_json::to_writer(&mut *out, &self.json_node(root, 0, filter))?;
+        writeln!(out)
+    }
+
+    fn json_node(&self, node_id: NodeId, depth: usize, filter: ExportFilter) -> JsonNode {
+        let node = &self[node_id];
+        JsonNode {
+            action: node.action.as_ref().map(|action| format!("{:?}", action)),
+            wins: Some(node.wins).filter(|wins| wins.is_finite()),
+            visits: node.visits,
+            player_just_moved: node.player_just_moved,
+            children: node.children.iter()
+                .filter(|child| filter.includes(self[**child].visits, depth + 1))
+                .map(|child| self.json_node(*child, depth + 1, filter))
+                .collect(),
+        }
+    }
+}
+
+/// A node as `write_json` writes it
+#[derive(Serialize)]
+struct JsonNode {
+    action: Option<String>,
+    wins: Option<f32>,
+    visits: u32,
+    player_just_moved: usize,
+    children: Vec<JsonNode>,
+}
+
+/// `text` with the characters that cannot appear inside a DOT string escaped. DOT has no escape
+/// for other control characters, so they are replaced with a space.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,8 +1,34 @@
+extern crate rand;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
 pub mod node;
 pub mod action;
 pub mod state;
+pub mod policy;
+pub mod search;
+pub mod result;
+pub mod ponder;
+pub mod export;
+pub mod save;
+pub mod book;
+pub mod evaluator;
+pub mod selfplay;
+pub mod rng;
 
 pub use node::*;
+pub use search::*;
+pub use result::*;
+pub use ponder::*;
+pub use export::*;
+pub use save::*;
+pub use book::*;
+pub use evaluator::*;
+pub use selfplay::*;
+pub use policy::*;
+pub use rng::*;
 
 #[cfg(test)]
 mod tests {
@@ -1,64 +1,196 @@
 use action::Action;
 use state::State;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use std::mem;
 use std::ops::{Index, IndexMut};
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Serialize, Deserialize)]
 pub struct NodeId {
     index: usize
 }
 
-#[derive(Debug)]
-pub struct NodeArena {
-    nodes: Vec<Node>
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NodeArena<A> {
+    nodes: Vec<Node<A>>,
+    /// Statistics of every position reached by the search, by `Node::hash`. Only filled when
+    /// the search shares transpositions, and kept when the tree is rerooted.
+    pub transpositions: HashMap<u64, Transposition>,
 }
 
-impl NodeArena {
-    pub fn new() -> NodeArena {
-        NodeArena { nodes: Vec::new() }
+impl<A> Default for NodeArena<A> {
+    fn default() -> NodeArena<A> {
+        NodeArena::new()
     }
+}
 
-    pub fn new_node<S: State>(&mut self, state: S) -> NodeId {
-        let index = self.nodes.len();
+impl<A> NodeArena<A> {
+    pub fn new() -> NodeArena<A> {
+        NodeArena { nodes: Vec::new(), transpositions: HashMap::new() }
+    }
 
-        self.nodes.push(Node {
-            id: NodeId { index: index },
-            action: None,
-            action_string: None,
-            parent: None,
-            children: Vec::new(),
-            wins: 0.0,
-            visits: 0,
-            untried_actions: state.get_actions(),
-            untried_action_strings: state.get_action_strings(),
-            player_just_moved: state.get_player_just_moved()
-        });
+    /// Number of nodes currently stored in the arena
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
 
-        NodeId{ index: index }
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
     }
 
-    pub fn new_child_node<S: State>(&mut self, parent: Option<NodeId>, action: Option<u32>, action_string: Option<String>, state: &S) -> NodeId {
+    /// Returns true if the nodes form one tree below `root`, as in any arena the search built:
+    /// every node's id is its own index, the root has no parent, each child's parent is the node
+    /// listing it, and every node is reached from the root exactly once
+    pub fn is_tree(&self, root: NodeId) -> bool {
+        let len = self.nodes.len();
+        if root.index >= len || self.nodes[root.index].parent.is_some()
+            || self.nodes.iter().enumerate().any(|(index, node)| node.id.index != index) {
+            return false;
+        }
+
+        let mut reached = vec!(false; len);
+        reached[root.index] = true;
+        let mut count = 1;
+        let mut stack = vec!(root);
+        while let Some(node_id) = stack.pop() {
+            for child in &self.nodes[node_id.index].children {
+                if child.index >= len || reached[child.index] || self.nodes[child.index].parent != Some(node_id) {
+                    return false;
+                }
+                reached[child.index] = true;
+                count += 1;
+                stack.push(*child);
+            }
+        }
+        count == len
+    }
+
+    pub fn new_node<S: State<Action = A>>(&mut self, state: S) -> NodeId {
+        self.new_child_node(None, None, &state)
+    }
+
+    pub fn new_child_node<S: State<Action = A>>(&mut self, parent: Option<NodeId>, action: Option<A>, state: &S) -> NodeId {
         let index = self.nodes.len();
 
         self.nodes.push(Node {
-            id: NodeId { index: index },
-            action: action,
-            action_string: action_string,
-            parent: parent,
+            id: NodeId { index },
+            action,
+            parent,
             children: Vec::new(),
             wins: 0.0,
             visits: 0,
+            availability: 0,
+            sum_squares: 0.0,
+            amaf_wins: 0.0,
+            amaf_visits: 0,
+            prior: 1.0,
+            chance: !state.get_chance_outcomes().is_empty(),
+            hash: None,
             untried_actions: state.get_actions(),
-            untried_action_strings: state.get_action_strings(),
+            untried_priors: Vec::new(),
             player_just_moved: state.get_player_just_moved()
         });
 
-        NodeId{ index: index }
+        NodeId { index }
+    }
+
+    /// Make `node` the root of the tree and drop every node that is not below it. The remaining
+    /// nodes are compacted, which invalidates every NodeId into this arena - the root is returned
+    /// with its new id.
+    pub fn reroot(&mut self, node: NodeId) -> NodeId {
+        // Breadth first order of the kept subtree gives each kept node its new index
+        let mut new_index = vec!(None; self.nodes.len());
+        let mut order = vec!(node);
+        let mut i = 0;
+        while i < order.len() {
+            new_index[order[i].index] = Some(i);
+            order.extend(self.nodes[order[i].index].children.iter().cloned());
+            i += 1;
+        }
+
+        let remap = |id: NodeId| new_index[id.index].map(|index| NodeId { index });
+        let mut old_nodes: Vec<Option<Node<A>>> = mem::take(&mut self.nodes).into_iter().map(Some).collect();
+        for old_id in order {
+            let mut node = old_nodes[old_id.index].take().expect("Node reachable twice while rerooting");
+            node.id = remap(old_id).expect("Kept node without new index");
+            node.parent = node.parent.and_then(remap);
+            node.children = node.children.iter().filter_map(|child| remap(*child)).collect();
+            self.nodes.push(node);
+        }
+
+        let root = &mut self.nodes[0];
+        root.action = None;
+        root.id
+    }
+
+    /// Count a visit without a reward on `node` and every ancestor, so other threads sharing the
+    /// tree see this path as less promising until the real result is backpropagated
+    pub fn add_virtual_loss(&mut self, node: NodeId) {
+        let mut curr_node = Some(node);
+        while let Some(node_id) = curr_node {
+            self[node_id].visits += 1;
+            curr_node = self[node_id].parent;
+        }
+    }
+
+    /// Undo `add_virtual_loss` on `node` and every ancestor
+    pub fn remove_virtual_loss(&mut self, node: NodeId) {
+        let mut curr_node = Some(node);
+        while let Some(node_id) = curr_node {
+            self[node_id].visits -= 1;
+            curr_node = self[node_id].parent;
+        }
+    }
+
+}
+
+impl<A: Action> NodeArena<A> {
+    /// Size and shape of the tree below `root`
+    pub fn tree_stats(&self, root: NodeId) -> TreeStats<A> {
+        // Breadth first, so the nodes of each depth come after those of the depth above
+        let mut order = vec!((root, 0));
+        let mut i = 0;
+        while i < order.len() {
+            let (node, depth) = order[i];
+            order.extend(self[node].children.iter().map(|child| (*child, depth + 1)));
+            i += 1;
+        }
+
+        let max_depth = order.last().map_or(0, |&(_, depth)| depth);
+        let mut nodes_per_depth = vec!(0; max_depth + 1);
+        let mut bytes = 0;
+        for &(node, depth) in &order {
+            nodes_per_depth[depth] += 1;
+            let node = &self[node];
+            bytes += mem::size_of::<Node<A>>()
+                + node.children.capacity() * mem::size_of::<NodeId>()
+                + node.untried_actions.capacity() * mem::size_of::<A>()
+                + node.untried_priors.capacity() * mem::size_of::<f32>();
+        }
+
+        let mut most_visited_path = Vec::new();
+        let mut node = root;
+        while let Some(child) = self[node].children.iter().max_by_key(|child| self[**child].visits) {
+            if self[*child].visits == 0 {
+                break;
+            }
+            most_visited_path.extend(self[*child].action.clone());
+            node = *child;
+        }
+
+        TreeStats {
+            nodes: order.len(),
+            max_depth,
+            average_depth: order.iter().map(|&(_, depth)| depth as f64).sum::<f64>() / order.len() as f64,
+            branching_factors: nodes_per_depth.windows(2).map(|pair| pair[1] as f64 / pair[0] as f64).collect(),
+            bytes,
+            most_visited_path,
+        }
     }
 
     pub fn as_tree(&self) -> String {
         let rootnode = &self.nodes[0];
-        let display_str = self.display_node(rootnode.id, 0);
-        display_str
+        self.display_node(rootnode.id, 0)
     }
 
     pub fn simple_display(&self) -> String {
@@ -74,11 +206,11 @@ impl NodeArena {
     pub fn display_node(&self, node_id: NodeId, indent: usize) -> String {
         let node = &self.nodes[node_id.index];
         let mut display_str = String::from("\n");
-        for i in 1..indent+1 {
+        for _ in 0..indent {
             display_str.push_str("| ");
         }
         display_str.push_str(format!("{}", node).as_str());
-        
+
         for child in node.children.iter() {
             display_str.push_str(self.display_node(*child, indent+1).as_str());
         }
@@ -86,28 +218,26 @@ impl NodeArena {
     }
 }
 
-impl Index<NodeId> for NodeArena {
-    type Output = Node;
+impl<A> Index<NodeId> for NodeArena<A> {
+    type Output = Node<A>;
 
-    fn index(&self, node: NodeId) -> &Node {
+    fn index(&self, node: NodeId) -> &Node<A> {
         &self.nodes[node.index]
     }
 }
 
-impl IndexMut<NodeId> for NodeArena {
-    fn index_mut(&mut self, node: NodeId) -> &mut Node {
+impl<A> IndexMut<NodeId> for NodeArena<A> {
+    fn index_mut(&mut self, node: NodeId) -> &mut Node<A> {
         &mut self.nodes[node.index]
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Node {
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Node<A> {
     /// Id of the node itself to find itself in the NodeArena
     pub id: NodeId,
     /// Action that got us to this node - None for root
-    pub action: Option<u32>,
-    /// Action that got us to this node - None for root
-    pub action_string: Option<String>,
+    pub action: Option<A>,
     /// Parent node - None for root
     pub parent: Option<NodeId>,
     /// Children nodes
@@ -116,37 +246,209 @@ pub struct Node {
     pub wins: f32,
     /// Number of visits for this node
     pub visits: u32,
-    /// Vector of actions left to take 
-    pub untried_actions: Vec<u32>,
-    /// Vector of the string representation of the actions left to take
-    pub untried_action_strings: Vec<String>,
+    /// Number of times this node's action was legal when selection passed its parent, which
+    /// information set search uses in place of the parent's visits
+    pub availability: u32,
+    /// Sum of the squared results for this node, used to estimate reward variance
+    pub sum_squares: f32,
+    /// Sum of the results of every iteration in which the player who moved into this node played
+    /// its action at any later point after the parent, all moves as first
+    pub amaf_wins: f32,
+    /// Number of iterations counted in `amaf_wins`
+    pub amaf_visits: u32,
+    /// Prior probability of choosing the action that got us to this node
+    pub prior: f32,
+    /// True if the game is waiting on a random event here - the children are the outcomes, which
+    /// are sampled rather than chosen
+    pub chance: bool,
+    /// Hash of the position this node leads to when the search shares transpositions. The node's
+    /// own statistics then count the visits along this path, and the position's statistics are
+    /// pooled in `NodeArena::transpositions`.
+    pub hash: Option<u64>,
+    /// Vector of actions left to take
+    pub untried_actions: Vec<A>,
+    /// Prior probability of each of `untried_actions`, in the same order, as predicted by the
+    /// search's evaluator. Empty when the node was not evaluated.
+    pub untried_priors: Vec<f32>,
     /// Number of the player who has just played
     pub player_just_moved: usize,
 }
 
-impl Node {
-    pub fn add_child<S: State>(&self, arena: &mut NodeArena, action: Option<u32>, action_string: Option<String>, state: S) -> NodeId {
-        let new_node = arena.new_child_node(Some(self.id), action, action_string, &state);
-        
+impl<A> Default for Node<A> {
+    fn default() -> Node<A> {
+        Node::new()
+    }
+}
+
+impl<A> Node<A> {
+    /// Create an empty, detached root node with no actions to try
+    pub fn new() -> Node<A> {
+        Node {
+            id: NodeId { index: 0 },
+            action: None,
+            parent: None,
+            children: Vec::new(),
+            wins: 0.0,
+            visits: 0,
+            availability: 0,
+            sum_squares: 0.0,
+            amaf_wins: 0.0,
+            amaf_visits: 0,
+            prior: 1.0,
+            chance: false,
+            hash: None,
+            untried_actions: Vec::new(),
+            untried_priors: Vec::new(),
+            player_just_moved: 0
+        }
+    }
 
-        new_node
+    pub fn add_child<S: State<Action = A>>(&self, arena: &mut NodeArena<A>, action: Option<A>, state: S) -> NodeId {
+        arena.new_child_node(Some(self.id), action, &state)
     }
 
     pub fn update(&mut self, result: f32) {
         self.visits += 1;
         self.wins += result;
+        self.sum_squares += result * result;
+    }
+
+    /// Add the statistics of the same node from another search tree
+    pub fn merge_stats(&mut self, other: &Node<A>) {
+        self.visits += other.visits;
+        self.availability += other.availability;
+        self.wins += other.wins;
+        self.sum_squares += other.sum_squares;
+        self.amaf_wins += other.amaf_wins;
+        self.amaf_visits += other.amaf_visits;
+    }
+
+    /// Snapshot of the statistics used by tree policies
+    pub fn stats(&self) -> NodeStats {
+        NodeStats {
+            wins: self.wins,
+            visits: self.visits,
+            sum_squares: self.sum_squares,
+            amaf_wins: self.amaf_wins,
+            amaf_visits: self.amaf_visits,
+            prior: self.prior
+        }
     }
 }
 
-impl ::std::fmt::Display for Node {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+/// Statistics of a node that a `TreePolicy` uses to score it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeStats {
+    pub wins: f32,
+    pub visits: u32,
+    pub sum_squares: f32,
+    pub amaf_wins: f32,
+    pub amaf_visits: u32,
+    pub prior: f32,
+}
+
+impl NodeStats {
+    /// Average result of the visits through this node, 0.0 if never visited
+    pub fn mean(&self) -> f64 {
+        if self.visits == 0 {
+            return 0.0;
+        }
+        self.wins as f64 / self.visits as f64
+    }
 
-        write!(f, "[({}) M: {:?} W/V: {}/{} A: {:?}]", 
-            self.id.index, 
-            self.clone().action_string.unwrap_or(String::from("None")), 
-            self.wins, 
-            self.visits, 
-            self.untried_action_strings)
+    /// Average result of the iterations that played this node's action at any point after the
+    /// parent, 0.0 if there were none
+    pub fn amaf_mean(&self) -> f64 {
+        if self.amaf_visits == 0 {
+            return 0.0;
+        }
+        self.amaf_wins as f64 / self.amaf_visits as f64
     }
 }
 
+/// Size and shape of a search tree, from `NodeArena::tree_stats`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeStats<A> {
+    /// Number of nodes in the tree, the root included
+    pub nodes: usize,
+    /// Number of moves from the root to the deepest node
+    pub max_depth: usize,
+    /// Mean number of moves from the root over every node
+    pub average_depth: f64,
+    /// Mean number of children of the nodes at each depth, starting with the root
+    pub branching_factors: Vec<f64>,
+    /// Estimate of the memory the nodes use, not counting memory owned by the actions themselves
+    pub bytes: usize,
+    /// Actions from the root following the most visited child at each node
+    pub most_visited_path: Vec<A>,
+}
+
+impl<A: Debug> Display for TreeStats<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} nodes using {} KiB", self.nodes, self.bytes / 1024)?;
+        writeln!(f, "Depth: max {}, average {:.2}", self.max_depth, self.average_depth)?;
+        writeln!(f, "Branching factor by depth: {:.2?}", self.branching_factors)?;
+        write!(f, "Most visited path: {:?}", self.most_visited_path)
+    }
+}
+
+/// Results of every visit to one position, whichever order of moves reached it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transposition {
+    pub visits: u32,
+    /// Sum of the results for every player, indexed by player number
+    pub wins: Vec<f32>,
+    /// Sum of the squared results for every player
+    pub sum_squares: Vec<f32>,
+}
+
+impl Transposition {
+    pub fn new(players: usize) -> Transposition {
+        Transposition {
+            visits: 0,
+            wins: vec!(0.0; players),
+            sum_squares: vec!(0.0; players),
+        }
+    }
+
+    pub fn update(&mut self, rewards: &[f32]) {
+        self.visits += 1;
+        for (player, reward) in rewards.iter().enumerate() {
+            self.wins[player] += reward;
+            self.sum_squares[player] += reward * reward;
+        }
+    }
+
+    /// Add the statistics of the same position from another search tree
+    pub fn merge(&mut self, other: &Transposition) {
+        self.visits += other.visits;
+        for player in 0..self.wins.len() {
+            self.wins[player] += other.wins[player];
+            self.sum_squares[player] += other.sum_squares[player];
+        }
+    }
+
+    /// Statistics of the position for `player`, as a tree policy scores them
+    pub fn stats(&self, player: usize, prior: f32) -> NodeStats {
+        NodeStats {
+            wins: self.wins[player],
+            visits: self.visits,
+            sum_squares: self.sum_squares[player],
+            amaf_wins: 0.0,
+            amaf_visits: 0,
+            prior
+        }
+    }
+}
+
+impl<A: Action> ::std::fmt::Display for Node<A> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+
+        write!(f, "[({}) M: {:?} W/V: {}/{} A: {:?}]",
+            self.id.index,
+            self.action,
+            self.wins,
+            self.visits,
+            self.untried_actions)
+    }
+}
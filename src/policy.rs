@@ -0,0 +1,307 @@
+use node::NodeStats;
+use rand::Rng;
+use rng::GameRng;
+use state::State;
+
+/// Scores children during the selection step. The child with the highest score is descended into.
+pub trait TreePolicy: Send + Sync {
+    fn score(&self, parent: &NodeStats, child: &NodeStats) -> f64;
+
+    /// Returns true if the policy scores with the all-moves-as-first statistics, which the search
+    /// only collects when asked to
+    fn uses_amaf(&self) -> bool {
+        false
+    }
+}
+
+/// UCB1: mean result plus an exploration bonus that shrinks as the child is visited
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ucb1 {
+    /// Weight of the exploration term
+    pub exploration: f64,
+}
+
+impl Ucb1 {
+    pub fn new(exploration: f64) -> Ucb1 {
+        Ucb1 { exploration }
+    }
+}
+
+impl Default for Ucb1 {
+    fn default() -> Ucb1 {
+        Ucb1::new(1.4)
+    }
+}
+
+impl TreePolicy for Ucb1 {
+    fn score(&self, parent: &NodeStats, child: &NodeStats) -> f64 {
+        if child.visits == 0 {
+            return f64::INFINITY;
+        }
+
+        let visits = child.visits as f64;
+        child.mean() + self.exploration * ((parent.visits as f64).ln() * 2.0 / visits).sqrt()
+    }
+}
+
+/// UCB1-Tuned: UCB1 with the exploration term bounded by the observed variance of the child's
+/// results, which explores less around children whose results are consistent
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Ucb1Tuned;
+
+impl Ucb1Tuned {
+    pub fn new() -> Ucb1Tuned {
+        Ucb1Tuned
+    }
+}
+
+impl TreePolicy for Ucb1Tuned {
+    fn score(&self, parent: &NodeStats, child: &NodeStats) -> f64 {
+        if child.visits == 0 {
+            return f64::INFINITY;
+        }
+
+        let visits = child.visits as f64;
+        let log_parent = (parent.visits as f64).ln();
+        let mean = child.mean();
+        let variance = child.sum_squares as f64 / visits - mean * mean
+            + (2.0 * log_parent / visits).sqrt();
+
+        mean + (log_parent / visits * variance.min(0.25)).sqrt()
+    }
+}
+
+/// PUCT as used by AlphaZero: the exploration bonus is weighted by the prior probability stored
+/// on each child, so children the prior favours are tried first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Puct {
+    /// Weight of the prior-driven exploration term
+    pub exploration: f64,
+}
+
+impl Puct {
+    pub fn new(exploration: f64) -> Puct {
+        Puct { exploration }
+    }
+}
+
+impl Default for Puct {
+    fn default() -> Puct {
+        Puct::new(1.5)
+    }
+}
+
+impl TreePolicy for Puct {
+    fn score(&self, parent: &NodeStats, child: &NodeStats) -> f64 {
+        let bonus = (parent.visits as f64).sqrt() / (1.0 + child.visits as f64);
+        child.mean() + self.exploration * child.prior as f64 * bonus
+    }
+}
+
+/// How much a RAVE score relies on the all-moves-as-first mean rather than the child's own mean.
+/// The weight starts at 1.0 and falls towards 0.0 as the child gathers visits of its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RaveSchedule {
+    /// `sqrt(k / (3 * visits + k))`, which weighs both means equally after `k` visits
+    Equivalence(f64),
+    /// The weight minimizing the mean squared error of the blend, given the bias of the
+    /// all-moves-as-first estimate
+    MinimumError(f64),
+}
+
+impl RaveSchedule {
+    /// Weight of the all-moves-as-first mean for a child with the given counts
+    pub fn beta(&self, visits: u32, amaf_visits: u32) -> f64 {
+        let visits = visits as f64;
+        let amaf_visits = amaf_visits as f64;
+        match *self {
+            RaveSchedule::Equivalence(k) => (k / (3.0 * visits + k)).sqrt(),
+            RaveSchedule::MinimumError(bias) => {
+                if amaf_visits == 0.0 {
+                    return 0.0;
+                }
+                amaf_visits / (visits + amaf_visits + 4.0 * bias * bias * visits * amaf_visits)
+            }
+        }
+    }
+}
+
+/// RAVE: UCB1 on a blend of the child's mean and its all-moves-as-first mean, which credits an
+/// action with the results of every iteration that played it later on. Converges much faster in
+/// games where the value of a move barely depends on when it is played.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rave {
+    /// Weight of the exploration term
+    pub exploration: f64,
+    /// Weight of the all-moves-as-first mean
+    pub schedule: RaveSchedule,
+}
+
+impl Rave {
+    pub fn new(exploration: f64, schedule: RaveSchedule) -> Rave {
+        Rave { exploration, schedule }
+    }
+}
+
+impl Default for Rave {
+    fn default() -> Rave {
+        Rave::new(0.4, RaveSchedule::Equivalence(300.0))
+    }
+}
+
+impl TreePolicy for Rave {
+    fn score(&self, parent: &NodeStats, child: &NodeStats) -> f64 {
+        if child.visits == 0 && child.amaf_visits == 0 {
+            return f64::INFINITY;
+        }
+
+        let beta = self.schedule.beta(child.visits, child.amaf_visits);
+        let value = (1.0 - beta) * child.mean() + beta * child.amaf_mean();
+        if child.visits == 0 {
+            return value;
+        }
+
+        let visits = child.visits as f64;
+        value + self.exploration * ((parent.visits as f64).ln() / visits).sqrt()
+    }
+
+    fn uses_amaf(&self) -> bool {
+        true
+    }
+}
+
+/// Limits how many children a node may have to `k * visits^alpha`, so a node with many actions
+/// is only widened as it proves worth visiting and the search can go deeper instead
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressiveWidening {
+    /// Number of children allowed on the first visits
+    pub k: f64,
+    /// How fast the allowed children grow with the visits, from 0.0 (never) to 1.0 (every visit)
+    pub alpha: f64,
+}
+
+impl ProgressiveWidening {
+    pub fn new(k: f64, alpha: f64) -> ProgressiveWidening {
+        ProgressiveWidening { k, alpha }
+    }
+
+    /// Number of children a node with `visits` visits may have, always at least one
+    pub fn max_children(&self, visits: u32) -> usize {
+        let allowed = (self.k * (visits as f64).powf(self.alpha)).ceil();
+        (allowed as usize).max(1)
+    }
+}
+
+impl Default for ProgressiveWidening {
+    fn default() -> ProgressiveWidening {
+        ProgressiveWidening::new(1.0, 0.5)
+    }
+}
+
+/// Scores playing an action in a state, higher is better for the player to move
+pub type ActionHeuristic<S> = dyn Fn(&S, &<S as State>::Action) -> f32 + Send + Sync;
+
+/// Scores an unfinished game for every player from 0.0 - 1.0, like `State::get_rewards`
+pub type StateEvaluator<S> = dyn Fn(&S) -> Vec<f32> + Send + Sync;
+
+/// Chooses the moves played during the rollout (default policy) step
+pub trait RolloutPolicy<S: State>: Send + Sync {
+    /// Pick the next action to play in `state` out of its legal `actions`
+    fn choose_action(&self, state: &S, actions: &[S::Action], rng: &mut GameRng) -> S::Action;
+
+    /// Number of plies after which the rollout stops early. None plays until the game is over.
+    fn max_plies(&self) -> Option<usize> {
+        None
+    }
+
+    /// Rewards of a rollout that was stopped before the game was over
+    fn evaluate(&self, state: &S) -> Vec<f32> {
+        state.get_rewards()
+    }
+}
+
+/// Plays uniformly random actions
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct UniformRollout;
+
+impl UniformRollout {
+    pub fn new() -> UniformRollout {
+        UniformRollout
+    }
+}
+
+impl<S: State> RolloutPolicy<S> for UniformRollout {
+    fn choose_action(&self, _state: &S, actions: &[S::Action], rng: &mut GameRng) -> S::Action {
+        actions[rng.gen_range(0, actions.len())].clone()
+    }
+}
+
+/// Plays the action with the highest heuristic value, except for a random action with
+/// probability `epsilon`
+pub struct EpsilonGreedyRollout<S: State> {
+    /// Probability of playing a uniformly random action instead of the greedy one
+    pub epsilon: f64,
+    heuristic: Box<ActionHeuristic<S>>,
+}
+
+impl<S: State> EpsilonGreedyRollout<S> {
+    pub fn new<F: Fn(&S, &S::Action) -> f32 + Send + Sync + 'static>(epsilon: f64, heuristic: F) -> EpsilonGreedyRollout<S> {
+        EpsilonGreedyRollout {
+            epsilon,
+            heuristic: Box::new(heuristic),
+        }
+    }
+}
+
+impl<S: State> RolloutPolicy<S> for EpsilonGreedyRollout<S> {
+    fn choose_action(&self, state: &S, actions: &[S::Action], rng: &mut GameRng) -> S::Action {
+        if rng.gen::<f64>() < self.epsilon {
+            return actions[rng.gen_range(0, actions.len())].clone();
+        }
+
+        let mut best_value = f32::NEG_INFINITY;
+        let mut best_action = &actions[0];
+        for action in actions {
+            let value = (self.heuristic)(state, action);
+            if value > best_value {
+                best_value = value;
+                best_action = action;
+            }
+        }
+        best_action.clone()
+    }
+}
+
+/// Plays another rollout policy for at most `plies` moves, then scores the unfinished game with a
+/// static evaluator instead of playing it out
+pub struct TruncatedRollout<S> {
+    /// Number of plies to play before calling the evaluator
+    pub plies: usize,
+    policy: Box<dyn RolloutPolicy<S>>,
+    evaluator: Box<StateEvaluator<S>>,
+}
+
+impl<S: State> TruncatedRollout<S> {
+    pub fn new<P, F>(policy: P, plies: usize, evaluator: F) -> TruncatedRollout<S>
+        where P: RolloutPolicy<S> + 'static, F: Fn(&S) -> Vec<f32> + Send + Sync + 'static {
+        TruncatedRollout {
+            plies,
+            policy: Box::new(policy),
+            evaluator: Box::new(evaluator),
+        }
+    }
+}
+
+impl<S: State> RolloutPolicy<S> for TruncatedRollout<S> {
+    fn choose_action(&self, state: &S, actions: &[S::Action], rng: &mut GameRng) -> S::Action {
+        self.policy.choose_action(state, actions, rng)
+    }
+
+    fn max_plies(&self) -> Option<usize> {
+        Some(self.plies)
+    }
+
+    fn evaluate(&self, state: &S) -> Vec<f32> {
+        (self.evaluator)(state)
+    }
+}
@@ -0,0 +1,47 @@
+use search::{Budget, Mcts, Parallelism};
+use state::State;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// A search running on a background thread, started by `Mcts::ponder`
+pub struct Ponder<S: State> {
+    stop_flag: Arc<AtomicBool>,
+    handle: thread::JoinHandle<Mcts<S>>,
+}
+
+impl<S: State> Ponder<S> {
+    /// End the background search and hand back the searcher with the tree it grew
+    pub fn stop(self) -> Mcts<S> {
+        self.stop_flag.store(true, Ordering::SeqCst);
+        self.handle.join().expect("Ponder thread panicked")
+    }
+}
+
+impl<S: State + Clone + Send + 'static> Mcts<S> {
+    /// Keep searching `state` on a background thread, ignoring the budget, until `stop` is called
+    /// on the returned handle - typically while the opponent thinks about their move. Passing
+    /// their move to `advance` afterwards keeps what was found about it for the next search.
+    ///
+    /// With more than one thread the threads share one tree whatever `parallelism` is set to,
+    /// since a root parallel search only keeps the root's children.
+    pub fn ponder(mut self, state: S) -> Ponder<S> {
+        let stop_flag = self.stop_handle();
+        let handle = thread::spawn(move || {
+            let budget = self.budget;
+            let parallelism = self.parallelism;
+            self.budget = Budget::Time(Duration::MAX);
+            self.parallelism = Parallelism::Tree;
+
+            // Stopping before the first iteration leaves nothing to choose from, which is fine
+            let _ = self.analyze(&state);
+
+            self.budget = budget;
+            self.parallelism = parallelism;
+            self
+        });
+
+        Ponder { stop_flag, handle }
+    }
+}
@@ -0,0 +1,54 @@
+use node::NodeStats;
+use std::fmt::{self, Debug, Display};
+use std::time::Duration;
+
+/// Everything a search found out about the position, to show why it chose its action
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult<A> {
+    /// Action picked by the searcher's final move strategy
+    pub action: A,
+    /// Statistics of every explored root child, most visited first
+    pub children: Vec<ChildStats<A>>,
+    /// Expected line of play, starting with `action` and then following the most visited child
+    pub principal_variation: Vec<A>,
+    /// Number of iterations the search ran
+    pub iterations: u32,
+    /// Wall-clock time the search took
+    pub elapsed: Duration,
+}
+
+/// Statistics of one root child
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildStats<A> {
+    pub action: A,
+    pub visits: u32,
+    /// Mean result of the player who plays the action
+    pub mean: f64,
+    /// Half the width of the 95% confidence interval around `mean`
+    pub confidence: f64,
+}
+
+impl<A> ChildStats<A> {
+    pub fn new(action: A, stats: &NodeStats) -> ChildStats<A> {
+        let visits = stats.visits as f64;
+        let mean = stats.mean();
+        let confidence = if stats.visits > 0 {
+            let variance = (stats.sum_squares as f64 / visits - mean * mean).max(0.0);
+            1.96 * (variance / visits).sqrt()
+        } else {
+            0.0
+        };
+
+        ChildStats { action, visits: stats.visits, mean, confidence }
+    }
+}
+
+impl<A: Debug> Display for SearchResult<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:?} after {} iterations in {:.2}s", self.action, self.iterations, self.elapsed.as_secs_f64())?;
+        for child in &self.children {
+            writeln!(f, "  {:?}: {} visits, mean {:.3} ± {:.3}", child.action, child.visits, child.mean, child.confidence)?;
+        }
+        write!(f, "  Expected line: {:?}", self.principal_variation)
+    }
+}
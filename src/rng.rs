@@ -0,0 +1,30 @@
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+/// Random number generator used by the search and passed to `State::do_action`, so a game and a
+/// search can be reproduced from a single seed
+pub type GameRng = XorShiftRng;
+
+/// Create a generator that always produces the same numbers for the same `seed`
+pub fn seeded_rng(seed: u64) -> GameRng {
+    // SplitMix64 spreads the seed over the whole XorShift state, which must not be all zero
+    let mut x = seed;
+    let mut words = [0u32; 4];
+    for pair in words.chunks_mut(2) {
+        x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = x;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        pair[0] = z as u32;
+        pair[1] = (z >> 32) as u32;
+    }
+    if words == [0; 4] {
+        words[0] = 1;
+    }
+    XorShiftRng::from_seed(words)
+}
+
+/// Create a generator seeded from the operating system's randomness
+pub fn random_rng() -> GameRng {
+    seeded_rng(::rand::thread_rng().gen())
+}
@@ -0,0 +1,111 @@
+use node::{NodeArena, NodeId};
+use search::Mcts;
+use serde::Serialize;
+use serde::de::{self, DeserializeOwned};
+use serde_json::{self, Value};
+use state::State;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+/// Version of the file format written by `save_tree`. Files of any other version are refused.
+pub const TREE_FORMAT_VERSION: u64 = 1;
+
+/// Why a tree or an opening book could not be saved or loaded
+#[derive(Debug)]
+pub enum SaveError {
+    /// There is no tree to save, because nothing was searched yet
+    NoTree,
+    /// The file was written by a different version of the format
+    Version(u64),
+    /// Reading or writing the file failed
+    Io(io::Error),
+    /// The file is not a valid tree or book of this game
+    Format(serde_json::Error),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SaveError::NoTree => write!(f, "No tree has been grown yet"),
+            SaveError::Version(version) => write!(f, "File format version {} is not supported", version),
+            SaveError::Io(ref err) => write!(f, "Failed to access the file: {}", err),
+            SaveError::Format(ref err) => write!(f, "Invalid file: {}", err),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> SaveError {
+        SaveError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> SaveError {
+        if err.is_io() {
+            SaveError::Io(err.into())
+        } else {
+            SaveError::Format(err)
+        }
+    }
+}
+
+/// Read a JSON file written with a `version` field, refusing any version but `version`
+pub(crate) fn read_versioned<R: Read>(reader: R, version: u64) -> Result<Value, SaveError> {
+    let value: Value = serde_json::from_reader(reader)?;
+    let file_version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if file_version != version {
+        return Err(SaveError::Version(file_version));
+    }
+    Ok(value)
+}
+
+/// Layout of a tree file as written
+#[derive(Serialize)]
+struct TreeFileRef<'a, S: 'a, A: 'a> {
+    version: u64,
+    state: &'a S,
+    root: NodeId,
+    arena: &'a NodeArena<A>,
+}
+
+/// Layout of a tree file as read back
+#[derive(Deserialize)]
+struct TreeFile<S, A> {
+    state: S,
+    root: NodeId,
+    arena: NodeArena<A>,
+}
+
+impl<S> Mcts<S>
+    where S: State + Clone + Send + Serialize + DeserializeOwned, S::Action: Serialize + DeserializeOwned {
+    /// Write the tree and `rootstate`, the state it was grown for, as versioned JSON. Long
+    /// searches can be run offline this way and shipped with the game.
+    pub fn save_tree<W: Write>(&self, rootstate: &S, writer: W) -> Result<(), SaveError> {
+        let root = self.root.ok_or(SaveError::NoTree)?;
+        let file = TreeFileRef {
+            version: TREE_FORMAT_VERSION,
+            state: rootstate,
+            root,
+            arena: &self.arena,
+        };
+        serde_json::to_writer(writer, &file)?;
+        Ok(())
+    }
+
+    /// Replace the tree with one written by `save_tree` and return the state it was grown for.
+    /// Searching that state continues from the loaded statistics, provided the searcher is set
+    /// up the same way, such as sharing transpositions, as the one that saved it.
+    pub fn load_tree<R: Read>(&mut self, reader: R) -> Result<S, SaveError> {
+        let value = read_versioned(reader, TREE_FORMAT_VERSION)?;
+        let file: TreeFile<S, S::Action> = serde_json::from_value(value)?;
+        if !file.arena.is_tree(file.root) {
+            return Err(SaveError::Format(de::Error::custom("nodes do not form a tree below the root")));
+        }
+        self.resume(file.arena, file.root);
+        Ok(file.state)
+    }
+}
@@ -0,0 +1,1290 @@
+use book::OpeningBook;
+use evaluator::{Evaluation, Evaluator};
+use node::{NodeArena, NodeId, NodeStats, Transposition};
+use std::collections::HashSet;
+use policy::{ActionHeuristic, ProgressiveWidening, RolloutPolicy, TreePolicy, Ucb1, UniformRollout};
+use rand::Rng;
+use result::{ChildStats, SearchResult};
+use rng::{random_rng, seeded_rng, GameRng};
+use state::{Determinizable, State, StateHash};
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How much work a search is allowed to do before picking an action
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    /// Run exactly this many iterations
+    Iterations(u32),
+    /// Run until this much wall-clock time has passed
+    Time(Duration),
+    /// Stop at whichever of the two limits is reached first
+    IterationsOrTime(u32, Duration),
+}
+
+impl Budget {
+    /// Returns true once `iterations` run in `elapsed` time have used up the budget
+    pub fn exhausted(&self, iterations: u32, elapsed: Duration) -> bool {
+        match *self {
+            Budget::Iterations(max) => iterations >= max,
+            Budget::Time(max) => elapsed >= max,
+            Budget::IterationsOrTime(max_iterations, max_time) => {
+                iterations >= max_iterations || elapsed >= max_time
+            }
+        }
+    }
+
+    /// The same kind of limit, scaled down to `fraction` of each amount
+    fn fraction(&self, fraction: f64) -> Budget {
+        let iterations = |max: u32| (max as f64 * fraction) as u32;
+        match *self {
+            Budget::Iterations(max) => Budget::Iterations(iterations(max)),
+            Budget::Time(max) => Budget::Time(max.mul_f64(fraction)),
+            Budget::IterationsOrTime(max_iterations, max_time) => {
+                Budget::IterationsOrTime(iterations(max_iterations), max_time.mul_f64(fraction))
+            }
+        }
+    }
+
+    /// Estimate of the iterations left after running `iterations` in `elapsed` time, assuming
+    /// the rest run as fast as those did
+    pub fn remaining_iterations(&self, iterations: u32, elapsed: Duration) -> u32 {
+        let by_time = |max: Duration| {
+            let elapsed_secs = elapsed.as_secs_f64();
+            if iterations == 0 || elapsed_secs == 0.0 {
+                return u32::MAX;
+            }
+            let remaining_secs = max.checked_sub(elapsed).unwrap_or_default().as_secs_f64();
+            (remaining_secs * iterations as f64 / elapsed_secs) as u32
+        };
+
+        match *self {
+            Budget::Iterations(max) => max.saturating_sub(iterations),
+            Budget::Time(max) => by_time(max),
+            Budget::IterationsOrTime(max_iterations, max_time) => {
+                max_iterations.saturating_sub(iterations).min(by_time(max_time))
+            }
+        }
+    }
+}
+
+/// How the work is split when a search runs on more than one thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parallelism {
+    /// Every thread grows its own tree and the statistics of the root children are merged once
+    /// the budget is spent
+    Root,
+    /// All threads grow one shared tree. A virtual loss on the path a thread is exploring steers
+    /// the other threads towards different nodes.
+    Tree,
+}
+
+/// Share of the budget, in iterations and time, that `FinalMove::MaxRobust` may spend after it
+/// looking for a robust child
+pub const ROBUST_EXTENSION: f64 = 0.5;
+
+/// How the action is picked out of the root's children once the budget is spent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FinalMove {
+    /// The child with the most visits
+    MostVisits,
+    /// The child with the highest mean result
+    HighestMean,
+    /// The child with both the most visits and the highest mean. When no child leads both, the
+    /// search runs on for up to `ROBUST_EXTENSION` of its budget again until one does, and falls
+    /// back to the most visits.
+    MaxRobust,
+    /// The child with the highest `mean - c / sqrt(visits)`, which avoids moves that only look
+    /// good from a few visits
+    LowerConfidenceBound(f64),
+}
+
+/// Why a search could not pick an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchError {
+    /// The root has no explored children, because the game is over or the budget allowed no
+    /// iterations
+    NoActions,
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SearchError::NoActions => write!(f, "No actions were explored from the root"),
+        }
+    }
+}
+
+impl Error for SearchError {}
+
+/// Which trees an information set search grows for a game with hidden information
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Observers {
+    /// One tree of the searching player's information sets, which decides every player's moves
+    Single,
+    /// One tree per player of their own information sets, following the actions as that player
+    /// observes them. Each player's moves are decided in their own tree, so they cannot act on
+    /// what only the searching player knows.
+    Multi,
+}
+
+/// How the statistics of a position reached by several orders of moves are combined during
+/// selection. Each tree node keeps the visits along its own path, while the position's results
+/// are pooled over every path that reaches it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranspositionUpdate {
+    /// A child is scored by the pooled mean result of its position, explored by the visits along
+    /// its own path
+    Uct1,
+    /// Like `Uct1`, with the parent's visits also pooled over every path reaching its position
+    Uct2,
+    /// Like `Uct2`, with a child's mean backed up from the pooled means of the positions below
+    /// it, weighted by their visits, so a result found through one path reaches every path
+    Uct3,
+}
+
+/// Hash function of a `StateHash` game, recorded when transpositions are shared
+struct TranspositionMode<S> {
+    update: TranspositionUpdate,
+    hash: fn(&S) -> u64,
+}
+
+/// Opening book of a `StateHash` game, recorded when the searcher is given one
+struct BookLookup<S: State> {
+    book: OpeningBook<S::Action>,
+    hash: fn(&S) -> u64,
+}
+
+/// Functions of a `Determinizable` game, recorded when information set search is turned on
+struct Determinizer<S: State> {
+    observers: Observers,
+    determinize: fn(&S, usize, &mut GameRng) -> S,
+    player_to_move: fn(&S) -> usize,
+    num_players: fn(&S) -> usize,
+    observe_action: fn(&S, &S::Action, usize) -> S::Action,
+}
+
+/// Moves of one iteration in the order they were played, as the player who made them and the
+/// action. Chance outcomes are None.
+type Played<A> = Vec<Option<(usize, A)>>;
+
+/// Hands out iterations to the search threads until the budget is spent or the search is stopped
+struct IterationCounter {
+    claimed: AtomicU32,
+    completed: AtomicU32,
+    stopped: AtomicBool,
+}
+
+impl IterationCounter {
+    fn new() -> IterationCounter {
+        IterationCounter {
+            claimed: AtomicU32::new(0),
+            completed: AtomicU32::new(0),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    /// Reserve the next iteration, returns false if the budget does not allow it or the search
+    /// was stopped, by `stop` or by the caller's stop flag
+    fn claim(&self, budget: Budget, begin_time: Instant, stop_flag: &AtomicBool) -> bool {
+        if self.stopped.load(Ordering::SeqCst) || stop_flag.load(Ordering::SeqCst) {
+            return false;
+        }
+        let iteration = self.claimed.fetch_add(1, Ordering::SeqCst);
+        !budget.exhausted(iteration, begin_time.elapsed())
+    }
+
+    /// End the search once the iterations in progress are done
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    fn complete(&self) {
+        self.completed.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn completed(&self) -> u32 {
+        self.completed.load(Ordering::SeqCst)
+    }
+}
+
+/// Monte Carlo Tree Search. Children are selected with UCB1 and rollouts play uniformly random
+/// actions unless another `tree_policy` or `rollout_policy` is set.
+pub struct Mcts<S: State> {
+    /// Search tree built by the last call to `search`
+    pub arena: NodeArena<S::Action>,
+    /// Root of the search tree - None before the first search or once the tree is dropped
+    pub root: Option<NodeId>,
+    /// Limit on how long each search runs
+    pub budget: Budget,
+    /// Number of iterations run by the last search, not counting any done before the tree was
+    /// reused
+    pub iterations: u32,
+    /// Scores children during selection
+    pub tree_policy: Box<dyn TreePolicy>,
+    /// Plays out the game from newly expanded nodes
+    pub rollout_policy: Box<dyn RolloutPolicy<S>>,
+    /// Number of threads searching at once
+    pub threads: usize,
+    /// How the threads share the work when `threads` is more than 1
+    pub parallelism: Parallelism,
+    /// How the action is picked from the root's children
+    pub final_move: FinalMove,
+    /// End a search early once the runner-up root child cannot reach the visits of the most
+    /// visited one in the budget left, or right away when there is only one action. In a root
+    /// parallel search each thread judges its own tree against its share of the budget left.
+    pub stop_early: bool,
+    /// Limits the children of each node by its visits. None expands every action of a node before
+    /// selecting among its children.
+    pub widening: Option<ProgressiveWidening>,
+    /// Largest number of nodes the search may hold. Once it is reached iterations roll out from
+    /// the first move that would need a new node, so the tree stops growing.
+    pub max_nodes: Option<usize>,
+    /// Non-negative weight of each action in a state. Untried actions are expanded highest weight
+    /// first instead of at random, and the weights normalized over the legal actions become the
+    /// children's priors.
+    pub action_prior: Option<Box<ActionHeuristic<S>>>,
+    /// Scores leaves and predicts the priors of their actions in place of rollouts. Untried
+    /// actions are expanded highest prior first and the priors are stored on the children for
+    /// PUCT. None plays out the game with `rollout_policy`.
+    pub evaluator: Option<Box<dyn Evaluator<S>>>,
+    /// Most leaves handed to the evaluator at once. A single threaded search descends this many
+    /// times, using virtual loss to spread them out, before evaluating them together, while each
+    /// thread of a parallel search evaluates its own leaves one at a time.
+    pub batch_size: u32,
+    /// Source of all randomness in the search. A single threaded search with an iteration budget
+    /// is reproducible when this is created with `seeded_rng`.
+    pub rng: GameRng,
+    /// Set by `information_sets` to search a game with hidden information
+    determinizer: Option<Determinizer<S>>,
+    /// Set by `transpositions` to pool the statistics of equal positions
+    transposition: Option<TranspositionMode<S>>,
+    /// Set by `opening_book` to play known positions without searching
+    book: Option<BookLookup<S>>,
+    /// Ends the running search when set, see `stop_handle`
+    stop_flag: Arc<AtomicBool>,
+    /// Roots of every tree grown for the position given to `start`, empty before
+    roots: Vec<NodeId>,
+    /// Set when `root` was reached through `advance` or given to `resume`, so the next `start`
+    /// may continue from it. Any other tree was grown for a position that may have nothing to do
+    /// with the next one searched.
+    reusable: bool,
+}
+
+impl<S: State + Clone + Send> Mcts<S> {
+    pub fn new(budget: Budget) -> Mcts<S> {
+        Mcts {
+            arena: NodeArena::new(),
+            root: None,
+            budget,
+            iterations: 0,
+            tree_policy: Box::new(Ucb1::default()),
+            rollout_policy: Box::new(UniformRollout),
+            threads: 1,
+            parallelism: Parallelism::Root,
+            final_move: FinalMove::MostVisits,
+            stop_early: false,
+            widening: None,
+            max_nodes: None,
+            action_prior: None,
+            evaluator: None,
+            batch_size: 1,
+            rng: random_rng(),
+            determinizer: None,
+            transposition: None,
+            book: None,
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            roots: Vec::new(),
+            reusable: false,
+        }
+    }
+
+    /// Search from `rootstate` until the budget is spent and return the root child's action that
+    /// `final_move` picks. Fails if the root has no children to choose from.
+    ///
+    /// The tree kept by `advance` or given to `resume` is searched further if its root matches
+    /// `rootstate`, otherwise the search starts from a fresh tree.
+    ///
+    /// `rootstate` should be waiting on a player's decision. Chance events are played out by the
+    /// game itself, passing the outcome to `advance` like any other action.
+    pub fn search(&mut self, rootstate: &S) -> Result<S::Action, SearchError> {
+        self.analyze(rootstate).map(|result| result.action)
+    }
+
+    /// Search like `search`, returning the statistics of the root's children and the expected
+    /// line of play along with the action
+    pub fn analyze(&mut self, rootstate: &S) -> Result<SearchResult<S::Action>, SearchError> {
+        if let Some(result) = self.book_move(rootstate) {
+            self.iterations = 0;
+            return Ok(result);
+        }
+
+        self.analyze_without_book(rootstate)
+    }
+
+    /// Search like `analyze` even in the positions the opening book knows
+    pub(crate) fn analyze_without_book(&mut self, rootstate: &S) -> Result<SearchResult<S::Action>, SearchError> {
+        let result = self.run_search(rootstate);
+        // A stop only ends the search it was meant for
+        self.stop_flag.store(false, Ordering::SeqCst);
+        result
+    }
+
+    fn run_search(&mut self, rootstate: &S) -> Result<SearchResult<S::Action>, SearchError> {
+        self.start(rootstate);
+        let roots = self.roots.clone();
+        let rootnode = roots[self.acting_tree(rootstate)];
+
+        let begin_time = Instant::now();
+        if self.threads <= 1 {
+            let mut arena = mem::take(&mut self.arena);
+            let mut rng = self.rng.clone();
+            let mut iterations = 0;
+            while !self.budget.exhausted(iterations, begin_time.elapsed())
+                && !self.stop_flag.load(Ordering::SeqCst)
+                && !self.decided(&arena, rootnode, iterations, begin_time.elapsed(), 1) {
+                let batch = self.budget.remaining_iterations(iterations, begin_time.elapsed())
+                    .clamp(1, self.batch_size.max(1));
+                iterations += self.iterate(&mut arena, &roots, rootstate, &mut rng, batch);
+            }
+            self.arena = arena;
+            self.rng = rng;
+            self.iterations = iterations;
+        } else {
+            match self.parallelism {
+                Parallelism::Root => self.search_root_parallel(rootnode, rootstate, begin_time),
+                Parallelism::Tree => self.search_tree_parallel(&roots, rootstate, begin_time),
+            }
+        }
+
+        if self.final_move == FinalMove::MaxRobust {
+            self.extend_to_robust_child(&roots, rootnode, rootstate);
+        }
+
+        let action = self.best_action(rootnode).ok_or(SearchError::NoActions)?;
+        Ok(self.result(rootnode, action, begin_time.elapsed()))
+    }
+
+    /// Set up the search of `rootstate` to run in steps instead of to a budget, reusing the tree
+    /// kept by `advance` like `search` does
+    pub fn start(&mut self, rootstate: &S) {
+        let roots = match self.root {
+            Some(root) if self.reusable && !self.multi_observer() && self.root_matches(root, rootstate) => vec!(root),
+            _ => {
+                let mut arena = NodeArena::new();
+                let roots = self.new_roots(&mut arena, rootstate);
+                self.arena = arena;
+                roots
+            }
+        };
+        self.root = Some(roots[self.acting_tree(rootstate)]);
+        self.iterations = 0;
+        self.roots = roots;
+        self.reusable = false;
+    }
+
+    /// Run up to `iterations` more iterations on this thread, or fewer if the search is stopped,
+    /// which clears the stop flag for the next step. `rootstate` must be the state given to
+    /// `start`. Does nothing before `start`.
+    pub fn step(&mut self, rootstate: &S, iterations: u32) {
+        if self.roots.is_empty() {
+            return;
+        }
+
+        let roots = mem::take(&mut self.roots);
+        let mut arena = mem::take(&mut self.arena);
+        let mut rng = self.rng.clone();
+        let mut done = 0;
+        while done < iterations && !self.stop_flag.load(Ordering::SeqCst) {
+            let batch = (iterations - done).min(self.batch_size.max(1));
+            done += self.iterate(&mut arena, &roots, rootstate, &mut rng, batch);
+        }
+        if done < iterations {
+            self.stop_flag.store(false, Ordering::SeqCst);
+        }
+        self.iterations += done;
+        self.arena = arena;
+        self.rng = rng;
+        self.roots = roots;
+    }
+
+    /// Action the search would pick with the statistics gathered so far
+    pub fn current_best(&self) -> Result<S::Action, SearchError> {
+        self.root.and_then(|root| self.best_action(root)).ok_or(SearchError::NoActions)
+    }
+
+    /// Flag that ends the running search when set to true, for example from another thread. The
+    /// search returns once its iterations in progress are done and clears the flag; a flag set
+    /// before a search starts ends that search right away.
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        self.stop_flag.clone()
+    }
+
+    /// Move the root of the tree to the child reached by `action` so the statistics below it are
+    /// kept for the next search, and free every other node. Call this for each action played in
+    /// the game, the opponents' included. The tree is dropped if `action` was never explored, and
+    /// always in a multi-observer search since the other players' trees are not kept.
+    pub fn advance(&mut self, action: &S::Action) {
+        let child = self.root.filter(|_| !self.multi_observer()).and_then(|root| {
+            self.arena[root].children.iter()
+                .find(|child| self.arena[**child].action.as_ref() == Some(action))
+                .cloned()
+        });
+
+        match child {
+            Some(child) => {
+                self.roots = Vec::new();
+                self.root = Some(self.arena.reroot(child));
+                self.reusable = true;
+            }
+            None => self.clear(),
+        }
+    }
+
+    /// Drop the tree, so the next search starts from scratch
+    pub fn clear(&mut self) {
+        self.arena = NodeArena::new();
+        self.root = None;
+        self.roots = Vec::new();
+        self.reusable = false;
+    }
+
+    /// The opening book's move for `rootstate`, if the searcher has a book and it knows the
+    /// position. Moves that are not legal in `rootstate`, from a hash collision, are ignored.
+    fn book_move(&self, rootstate: &S) -> Option<SearchResult<S::Action>> {
+        let lookup = self.book.as_ref()?;
+        let entry = lookup.book.moves.get(&(lookup.hash)(rootstate))?;
+        if !rootstate.get_actions().contains(&entry.action) {
+            return None;
+        }
+
+        Some(SearchResult {
+            action: entry.action.clone(),
+            children: vec!(entry.clone()),
+            principal_variation: vec!(entry.action.clone()),
+            iterations: 0,
+            elapsed: Duration::from_secs(0),
+        })
+    }
+
+    /// Replace the tree with `arena`, grown earlier from `root` - for example one read back by
+    /// `load_tree`. The next search continues from it if `root` matches the state searched.
+    pub fn resume(&mut self, arena: NodeArena<S::Action>, root: NodeId) {
+        self.arena = arena;
+        self.root = Some(root);
+        self.roots = Vec::new();
+        self.reusable = true;
+    }
+
+    /// Returns true if `rootnode` was created for a state with the same mover and actions as
+    /// `rootstate`, so its statistics can be reused
+    fn root_matches(&self, rootnode: NodeId, rootstate: &S) -> bool {
+        let node = &self.arena[rootnode];
+        if node.player_just_moved != rootstate.get_player_just_moved() {
+            return false;
+        }
+
+        let node_actions: HashSet<&S::Action> = node.children.iter()
+            .filter_map(|child| self.arena[*child].action.as_ref())
+            .chain(node.untried_actions.iter())
+            .collect();
+        let state_actions = rootstate.get_actions();
+        node_actions == state_actions.iter().collect()
+    }
+
+    /// Grow an independent tree on every thread, then add the statistics of each tree's root
+    /// children to the matching children of `rootnode`
+    fn search_root_parallel(&mut self, rootnode: NodeId, rootstate: &S, begin_time: Instant) {
+        let counter = IterationCounter::new();
+        let seeds: Vec<u64> = (0..self.threads).map(|_| self.rng.gen()).collect();
+
+        let trees: Vec<(NodeArena<S::Action>, NodeId)> = {
+            let this = &*self;
+            let counter = &counter;
+            thread::scope(|scope| {
+                let handles: Vec<_> = seeds.iter().map(|&seed| {
+                    let state = rootstate.clone();
+                    scope.spawn(move || {
+                        let mut rng = seeded_rng(seed);
+                        let mut arena = NodeArena::new();
+                        let roots = this.new_roots(&mut arena, &state);
+                        let root = roots[this.acting_tree(&state)];
+                        let shares = this.threads as u32;
+                        while counter.claim(this.budget, begin_time, &this.stop_flag) {
+                            this.iterate(&mut arena, &roots, &state, &mut rng, 1);
+                            counter.complete();
+                            if this.decided(&arena, root, counter.completed(), begin_time.elapsed(), shares) {
+                                counter.stop();
+                            }
+                        }
+                        (arena, root)
+                    })
+                }).collect();
+
+                handles.into_iter()
+                       .map(|handle| handle.join().expect("Search thread panicked"))
+                       .collect()
+            })
+        };
+
+        for (tree, tree_root) in &trees {
+            self.merge_root(rootnode, tree, *tree_root, rootstate);
+        }
+        self.iterations = counter.completed();
+    }
+
+    /// Add the statistics of `tree_root` and its children to `rootnode` and its children, creating
+    /// any child that `rootnode` does not have yet
+    fn merge_root(&mut self, rootnode: NodeId, tree: &NodeArena<S::Action>, tree_root: NodeId, rootstate: &S) {
+        for tree_child in &tree[tree_root].children {
+            let tree_child = &tree[*tree_child];
+            let action = tree_child.action.as_ref().expect("Non-root node without action");
+
+            let existing = self.arena[rootnode].children.iter()
+                .find(|child| self.arena[**child].action.as_ref() == Some(action))
+                .cloned();
+
+            let child = match existing {
+                Some(child) => child,
+                None => {
+                    let mut state = rootstate.clone();
+                    state.do_action(action.clone(), &mut self.rng);
+                    let mut arena = mem::take(&mut self.arena);
+                    let (child, _) = self.child_for(&mut arena, rootnode, action, tree_child.prior, &state);
+                    self.arena = arena;
+                    child
+                }
+            };
+
+            self.arena[child].merge_stats(tree_child);
+        }
+
+        self.arena[rootnode].merge_stats(&tree[tree_root]);
+        for (hash, transposition) in &tree.transpositions {
+            self.arena.transpositions.entry(*hash)
+                .or_insert_with(|| Transposition::new(transposition.wins.len()))
+                .merge(transposition);
+        }
+    }
+
+    /// Grow one tree shared by every thread. Selection and expansion happen under the tree's lock
+    /// while rollouts run concurrently.
+    fn search_tree_parallel(&mut self, roots: &[NodeId], rootstate: &S, begin_time: Instant) {
+        let rootnode = roots[self.acting_tree(rootstate)];
+        let counter = IterationCounter::new();
+        let shared_arena = Mutex::new(mem::take(&mut self.arena));
+        let seeds: Vec<u64> = (0..self.threads).map(|_| self.rng.gen()).collect();
+
+        {
+            let this = &*self;
+            let counter = &counter;
+            let shared_arena = &shared_arena;
+            thread::scope(|scope| {
+                for &seed in &seeds {
+                    let thread_state = rootstate.clone();
+                    scope.spawn(move || {
+                        let mut rng = seeded_rng(seed);
+                        while counter.claim(this.budget, begin_time, &this.stop_flag) {
+                            let mut state = this.iteration_state(&thread_state, &mut rng);
+                            let mut played = Vec::new();
+                            let leaves = {
+                                let mut arena = shared_arena.lock().expect("Search tree lock poisoned");
+                                let leaves = this.descend(&mut arena, roots.to_vec(), &mut state, &mut played, &mut rng);
+                                for leaf in &leaves {
+                                    arena.add_virtual_loss(*leaf);
+                                }
+                                leaves
+                            };
+
+                            let evaluation = this.evaluator.as_ref()
+                                .map(|evaluator| this.evaluate(&**evaluator, vec!(state.clone())).remove(0));
+                            let rollout_rewards = match evaluation {
+                                Some(_) => Vec::new(),
+                                None => this.rollout(&mut state, &mut played, &mut rng),
+                            };
+
+                            let mut arena = shared_arena.lock().expect("Search tree lock poisoned");
+                            let rewards = match evaluation {
+                                Some(evaluation) => this.apply_evaluation(&mut arena, &leaves, &state, evaluation),
+                                None => rollout_rewards,
+                            };
+                            for leaf in &leaves {
+                                arena.remove_virtual_loss(*leaf);
+                                this.backpropagate(&mut arena, *leaf, &rewards);
+                                this.update_amaf(&mut arena, *leaf, &played, &rewards);
+                            }
+                            counter.complete();
+                            if this.decided(&arena, rootnode, counter.completed(), begin_time.elapsed(), 1) {
+                                counter.stop();
+                            }
+                        }
+                    });
+                }
+            });
+        }
+
+        self.arena = shared_arena.into_inner().expect("Search tree lock poisoned");
+        self.iterations = counter.completed();
+    }
+
+    /// Returns true if `stop_early` is set and no other root child can overtake the most visited
+    /// one in the budget left after `iterations` run in `elapsed` time, split between `shares`
+    /// trees
+    fn decided(&self, arena: &NodeArena<S::Action>, rootnode: NodeId, iterations: u32, elapsed: Duration, shares: u32) -> bool {
+        if !self.stop_early {
+            return false;
+        }
+
+        let root = &arena[rootnode];
+        if root.children.len() == 1 && root.untried_actions.is_empty() {
+            return true;
+        }
+
+        let mut leader = 0;
+        let mut runner_up = 0;
+        for child in &root.children {
+            let visits = arena[*child].visits;
+            if visits > leader {
+                runner_up = leader;
+                leader = visits;
+            } else if visits > runner_up {
+                runner_up = visits;
+            }
+        }
+        let remaining = self.budget.remaining_iterations(iterations, elapsed) / shares;
+        leader > runner_up.saturating_add(remaining)
+    }
+
+    /// Run select, expand, rollout and backpropagate passes over the trees rooted at `roots` and
+    /// return how many ran - one with the rollout policy, or `batch` whose leaves are handed to
+    /// the evaluator together
+    fn iterate(&self, arena: &mut NodeArena<S::Action>, roots: &[NodeId], rootstate: &S, rng: &mut GameRng, batch: u32) -> u32 {
+        let evaluator = match self.evaluator {
+            Some(ref evaluator) => evaluator,
+            None => {
+                let mut state = self.iteration_state(rootstate, rng);
+                let mut played = Vec::new();
+                let leaves = self.descend(arena, roots.to_vec(), &mut state, &mut played, rng);
+                let rewards = self.rollout(&mut state, &mut played, rng);
+                for leaf in leaves {
+                    self.backpropagate(arena, leaf, &rewards);
+                    self.update_amaf(arena, leaf, &played, &rewards);
+                }
+                return 1;
+            }
+        };
+
+        // Virtual loss steers the later descents of the batch away from the earlier leaves
+        let mut descents = Vec::new();
+        for _ in 0..batch {
+            let mut state = self.iteration_state(rootstate, rng);
+            let mut played = Vec::new();
+            let leaves = self.descend(arena, roots.to_vec(), &mut state, &mut played, rng);
+            for leaf in &leaves {
+                arena.add_virtual_loss(*leaf);
+            }
+            descents.push((leaves, state, played));
+        }
+
+        let states = descents.iter().map(|(_, state, _)| state.clone()).collect();
+        let evaluations = self.evaluate(&**evaluator, states);
+        for ((leaves, state, played), evaluation) in descents.iter().zip(evaluations) {
+            let rewards = self.apply_evaluation(arena, leaves, state, evaluation);
+            for leaf in leaves {
+                arena.remove_virtual_loss(*leaf);
+                self.backpropagate(arena, *leaf, &rewards);
+                self.update_amaf(arena, *leaf, played, &rewards);
+            }
+        }
+        batch
+    }
+
+    /// Evaluate `states` in one call to `evaluator`, leaving out the finished games, which are
+    /// None
+    fn evaluate(&self, evaluator: &dyn Evaluator<S>, states: Vec<S>) -> Vec<Option<Evaluation>> {
+        let finished = |state: &S| state.get_actions().is_empty() && state.get_chance_outcomes().is_empty();
+        let unfinished: Vec<S> = states.iter().filter(|state| !finished(state)).cloned().collect();
+        let mut evaluations = evaluator.evaluate(&unfinished).into_iter();
+        states.iter()
+              .map(|state| if finished(state) { None } else { evaluations.next() })
+              .collect()
+    }
+
+    /// Store the priors of `evaluation` on the acting tree's leaf and return the values to back
+    /// up, or the rewards of the game when it is over
+    fn apply_evaluation(&self, arena: &mut NodeArena<S::Action>, leaves: &[NodeId], state: &S, evaluation: Option<Evaluation>) -> Vec<f32> {
+        match evaluation {
+            Some(evaluation) => {
+                self.set_priors(arena, leaves[self.acting_tree(state)], state, &evaluation.priors);
+                evaluation.values
+            }
+            None => state.get_rewards(),
+        }
+    }
+
+    /// Record `priors`, given in the order of `state`'s actions, for the untried actions of
+    /// `node` unless it already has them. The priors are normalized, falling back to uniform.
+    fn set_priors(&self, arena: &mut NodeArena<S::Action>, node: NodeId, state: &S, priors: &[f32]) {
+        if arena[node].chance || !arena[node].untried_priors.is_empty() {
+            return;
+        }
+
+        let actions = state.get_actions();
+        let total: f32 = priors.iter().map(|prior| prior.max(0.0)).sum();
+        let untried_priors = arena[node].untried_actions.iter()
+            .map(|action| {
+                if total <= 0.0 {
+                    return 1.0 / actions.len() as f32;
+                }
+                let prior = actions.iter().position(|legal| legal == action).and_then(|index| priors.get(index));
+                prior.map_or(0.0, |prior| prior.max(0.0) / total)
+            })
+            .collect();
+        arena[node].untried_priors = untried_priors;
+    }
+
+    /// Evaluator's prior of the untried `action` of `node`, if the node was evaluated
+    fn evaluated_prior(&self, arena: &NodeArena<S::Action>, node: NodeId, action: &S::Action) -> Option<f32> {
+        let node = &arena[node];
+        node.untried_actions.iter()
+            .position(|untried| untried == action)
+            .and_then(|index| node.untried_priors.get(index))
+            .cloned()
+    }
+
+    /// The state an iteration plays on - `rootstate` itself, or a determinization of it as seen
+    /// by the player to move in information set search
+    fn iteration_state(&self, rootstate: &S, rng: &mut GameRng) -> S {
+        match self.determinizer {
+            Some(ref determinizer) => {
+                let observer = (determinizer.player_to_move)(rootstate);
+                (determinizer.determinize)(rootstate, observer, rng)
+            }
+            None => rootstate.clone(),
+        }
+    }
+
+    fn multi_observer(&self) -> bool {
+        self.determinizer.as_ref().is_some_and(|determinizer| determinizer.observers == Observers::Multi)
+    }
+
+    /// Create the root of every tree the search grows: one per player in a multi-observer search,
+    /// otherwise a single one
+    fn new_roots(&self, arena: &mut NodeArena<S::Action>, rootstate: &S) -> Vec<NodeId> {
+        let trees = match self.determinizer {
+            Some(ref determinizer) if determinizer.observers == Observers::Multi => (determinizer.num_players)(rootstate),
+            _ => 1,
+        };
+        let roots: Vec<NodeId> = (0..trees).map(|_| {
+            let root = arena.new_node(rootstate.clone());
+            arena[root].hash = self.position_hash(rootstate);
+            root
+        }).collect();
+
+        // Every other node gets its priors when it is evaluated as a leaf
+        if let Some(ref evaluator) = self.evaluator {
+            if let Some(evaluation) = self.evaluate(&**evaluator, vec!(rootstate.clone())).remove(0) {
+                self.set_priors(arena, roots[self.acting_tree(rootstate)], rootstate, &evaluation.priors);
+            }
+        }
+        roots
+    }
+
+    /// Hash of the position in `state` when transpositions are shared. Information set search
+    /// does not share them, since its nodes stand for what a player has seen rather than a
+    /// position.
+    fn position_hash(&self, state: &S) -> Option<u64> {
+        match self.transposition {
+            Some(ref transposition) if self.determinizer.is_none() => Some((transposition.hash)(state)),
+            _ => None,
+        }
+    }
+
+    /// Index of the tree that decides the next move in `state`
+    fn acting_tree(&self, state: &S) -> usize {
+        match self.determinizer {
+            Some(ref determinizer) if determinizer.observers == Observers::Multi => (determinizer.player_to_move)(state),
+            _ => 0,
+        }
+    }
+
+    /// `action` played in `state` as the owner of `tree` sees it
+    fn observed_action(&self, state: &S, action: &S::Action, tree: usize) -> S::Action {
+        match self.determinizer {
+            Some(ref determinizer) if determinizer.observers == Observers::Multi => (determinizer.observe_action)(state, action, tree),
+            _ => action.clone(),
+        }
+    }
+
+    /// Walk down every tree from `nodes` in step with `state`, returning the node each tree ends
+    /// on. The tree of the player to move picks each action with the tree policy, or samples the
+    /// outcome at a chance node, and every tree follows the action as its owner observes it.
+    /// Stops once the acting tree gains a node for an untried action or outcome, or when the game
+    /// is over. The moves made are added to `played`.
+    fn descend(&self, arena: &mut NodeArena<S::Action>, mut nodes: Vec<NodeId>, state: &mut S, played: &mut Played<S::Action>, rng: &mut GameRng) -> Vec<NodeId> {
+        loop {
+            let acting_tree = self.acting_tree(state);
+            let node = nodes[acting_tree];
+
+            let chance = arena[node].chance;
+            let (action, prior) = if chance {
+                let outcome = match state.sample_chance_outcome(rng) {
+                    Some(outcome) => outcome,
+                    None => return nodes,
+                };
+                let probability = state.get_chance_outcomes().iter()
+                    .find(|(o, _)| *o == outcome)
+                    .map_or(0.0, |&(_, probability)| probability);
+                (outcome, probability)
+            } else {
+                // Untried actions were recorded for the state the node was created in, a
+                // determinization or hidden randomness might not allow all of them now
+                let legal_actions = state.get_actions();
+                let untried: Vec<S::Action> = arena[node].untried_actions.iter()
+                    .filter(|action| legal_actions.contains(action))
+                    .cloned()
+                    .collect();
+
+                let action = if self.can_widen(arena, node, &untried, &legal_actions) {
+                    self.untried_action(arena, node, state, &untried, rng)
+                } else {
+                    match self.best_child(arena, node, &legal_actions) {
+                        Some(action) => action,
+                        None => return nodes,
+                    }
+                };
+                let prior = self.action_prior_of(arena, node, state, &action, &legal_actions);
+                (action, prior)
+            };
+
+            let observed: Vec<S::Action> = (0..nodes.len())
+                .map(|tree| self.observed_action(state, &action, tree))
+                .collect();
+            let full = self.max_nodes.is_some_and(|max_nodes| arena.len() >= max_nodes);
+            if full && nodes.iter().zip(&observed).any(|(node, action)| !self.has_child(arena, *node, action)) {
+                return nodes;
+            }
+            let recorded = (!chance && self.tree_policy.uses_amaf()).then(|| action.clone());
+            state.do_action(action, rng);
+            self.record(played, state, recorded);
+
+            let mut expanded = false;
+            for (tree, node) in nodes.iter_mut().enumerate() {
+                let (child, created) = self.child_for(arena, *node, &observed[tree], prior, state);
+                *node = child;
+                expanded |= created && tree == acting_tree;
+            }
+            if expanded {
+                return nodes;
+            }
+        }
+    }
+
+    /// Returns true if `node` should gain a child for one of its `untried` actions rather than
+    /// select among the children it has
+    fn can_widen(&self, arena: &NodeArena<S::Action>, node: NodeId, untried: &[S::Action], legal_actions: &[S::Action]) -> bool {
+        if untried.is_empty() {
+            return false;
+        }
+
+        match self.widening {
+            Some(ref widening) => {
+                let legal_children = arena[node].children.iter()
+                    .filter(|child| arena[**child].action.as_ref().is_some_and(|action| legal_actions.contains(action)))
+                    .count();
+                legal_children < widening.max_children(arena[node].visits)
+            }
+            None => true,
+        }
+    }
+
+    /// Next of the `untried` actions of `node` to expand - the one with the highest evaluated
+    /// prior or prior weight, or a random one without either
+    fn untried_action(&self, arena: &NodeArena<S::Action>, node: NodeId, state: &S, untried: &[S::Action], rng: &mut GameRng) -> S::Action {
+        if !arena[node].untried_priors.is_empty() {
+            let mut best_prior = f32::NEG_INFINITY;
+            let mut best_action = &untried[0];
+            for action in untried {
+                let prior = self.evaluated_prior(arena, node, action).unwrap_or(0.0);
+                if prior > best_prior {
+                    best_prior = prior;
+                    best_action = action;
+                }
+            }
+            return best_action.clone();
+        }
+
+        match self.action_prior {
+            Some(ref action_prior) => {
+                let mut best_weight = f32::NEG_INFINITY;
+                let mut best_action = &untried[0];
+                for action in untried {
+                    let weight = action_prior(state, action);
+                    if weight > best_weight {
+                        best_weight = weight;
+                        best_action = action;
+                    }
+                }
+                best_action.clone()
+            }
+            None => untried[rng.gen_range(0, untried.len())].clone(),
+        }
+    }
+
+    /// Prior probability of playing `action` out of the `legal_actions` of `state`, the node's
+    /// evaluated prior if it has one
+    fn action_prior_of(&self, arena: &NodeArena<S::Action>, node: NodeId, state: &S, action: &S::Action, legal_actions: &[S::Action]) -> f32 {
+        if let Some(prior) = self.evaluated_prior(arena, node, action) {
+            return prior;
+        }
+
+        // Without any other knowledge every legal action is equally likely
+        let uniform = 1.0 / legal_actions.len() as f32;
+        match self.action_prior {
+            Some(ref action_prior) => {
+                let total: f32 = legal_actions.iter().map(|legal| action_prior(state, legal).max(0.0)).sum();
+                if total > 0.0 { action_prior(state, action).max(0.0) / total } else { uniform }
+            }
+            None => uniform,
+        }
+    }
+
+    /// Action of the child of `node` that the tree policy scores highest, out of the children
+    /// legal in the current state. Every legal child has its availability counted, which stands
+    /// in for the parent's visits in information set search where a child is not legal on every
+    /// visit of its parent.
+    fn best_child(&self, arena: &mut NodeArena<S::Action>, node: NodeId, legal_actions: &[S::Action]) -> Option<S::Action> {
+        let parent_stats = self.parent_stats(arena, node);
+        let children: Vec<NodeId> = arena[node].children.iter()
+            .filter(|child| arena[**child].action.as_ref().is_some_and(|action| legal_actions.contains(action)))
+            .cloned()
+            .collect();
+
+        let mut best_value = f64::NEG_INFINITY;
+        let mut best_action = None;
+        for child in children {
+            arena[child].availability += 1;
+            let mut parent_stats = parent_stats;
+            if self.determinizer.is_some() {
+                parent_stats.visits = arena[child].availability;
+            }
+
+            let curr_value = self.tree_policy.score(&parent_stats, &self.child_stats(arena, child));
+            if curr_value > best_value {
+                best_value = curr_value;
+                best_action = arena[child].action.clone();
+            }
+        }
+
+        best_action
+    }
+
+    /// Statistics of `node` as the parent in selection - its position's pooled visits under
+    /// `Uct2` and `Uct3`, otherwise the visits along its path
+    fn parent_stats(&self, arena: &NodeArena<S::Action>, node: NodeId) -> NodeStats {
+        let mut stats = arena[node].stats();
+        let pooled = match self.transposition {
+            Some(ref transposition) if transposition.update != TranspositionUpdate::Uct1 => self.pooled(arena, node),
+            _ => None,
+        };
+        if let Some(pooled) = pooled {
+            stats.visits = stats.visits.max(pooled.visits);
+        }
+        stats
+    }
+
+    /// Statistics of `child` as selection scores it. With shared transpositions the mean comes
+    /// from its position, scaled to the visits along its own path.
+    fn child_stats(&self, arena: &NodeArena<S::Action>, child: NodeId) -> NodeStats {
+        let mut stats = arena[child].stats();
+        let update = match self.transposition {
+            Some(ref transposition) => transposition.update,
+            None => return stats,
+        };
+
+        let pooled = match self.pooled(arena, child) {
+            Some(pooled) if pooled.visits > 0 => pooled,
+            _ => return stats,
+        };
+        let player = arena[child].player_just_moved;
+        let mut mean = pooled.mean();
+        if update == TranspositionUpdate::Uct3 {
+            // Back up the mean from the positions below, weighted by the visits along each path
+            let (weighted, visits) = arena[child].children.iter()
+                .filter_map(|grandchild| {
+                    let transposition = arena[*grandchild].hash.and_then(|hash| arena.transpositions.get(&hash))?;
+                    let visits = arena[*grandchild].visits as f64;
+                    Some((transposition.stats(player, 0.0).mean() * visits, visits))
+                })
+                .fold((0.0, 0.0), |(weighted, total), (value, visits)| (weighted + value, total + visits));
+            if visits > 0.0 {
+                mean = weighted / visits;
+            }
+        }
+
+        let visits = stats.visits as f64;
+        let squares_per_visit = pooled.sum_squares as f64 / pooled.visits as f64;
+        stats.wins = (mean * visits) as f32;
+        stats.sum_squares = (squares_per_visit * visits) as f32;
+        stats
+    }
+
+    /// Statistics pooled over every path to the position of `node`, for the player who moved
+    /// into it
+    fn pooled(&self, arena: &NodeArena<S::Action>, node: NodeId) -> Option<NodeStats> {
+        let node = &arena[node];
+        node.hash
+            .and_then(|hash| arena.transpositions.get(&hash))
+            .map(|transposition| transposition.stats(node.player_just_moved, node.prior))
+    }
+
+    fn has_child(&self, arena: &NodeArena<S::Action>, node: NodeId, action: &S::Action) -> bool {
+        arena[node].children.iter().any(|child| arena[*child].action.as_ref() == Some(action))
+    }
+
+    /// Child of `node` reached by `action`, created for `state` with the given prior if it does
+    /// not exist yet. The flag is true if the child was created.
+    fn child_for(&self, arena: &mut NodeArena<S::Action>, node: NodeId, action: &S::Action, prior: f32, state: &S) -> (NodeId, bool) {
+        let existing = arena[node].children.iter()
+            .find(|child| arena[**child].action.as_ref() == Some(action))
+            .cloned();
+        if let Some(child) = existing {
+            return (child, false);
+        }
+
+        let parent_node = &mut arena[node];
+        if let Some(index) = parent_node.untried_actions.iter().position(|n| n == action) {
+            parent_node.untried_actions.remove(index);
+            if index < parent_node.untried_priors.len() {
+                parent_node.untried_priors.remove(index);
+            }
+        }
+
+        let child = arena.new_child_node(Some(node), Some(action.clone()), state);
+        arena[child].prior = prior;
+        arena[child].hash = self.position_hash(state);
+        arena[node].children.push(child);
+        (child, true)
+    }
+
+    /// Play the rollout policy until the game is over or the policy's ply limit is reached and
+    /// return the rewards. Unfinished games are scored by the rollout policy's evaluator.
+    fn rollout(&self, state: &mut S, played: &mut Played<S::Action>, rng: &mut GameRng) -> Vec<f32> {
+        let mut plies = 0;
+        loop {
+            // Random events are resolved without counting as a ply
+            if let Some(outcome) = state.sample_chance_outcome(rng) {
+                state.do_action(outcome, rng);
+                self.record(played, state, None);
+                continue;
+            }
+
+            let actions = state.get_actions();
+            if actions.is_empty() {
+                return state.get_rewards();
+            }
+            // Choices within a move are played as part of the ply that started it, and a
+            // truncated rollout finishes the move before evaluating
+            let sub_decision = state.in_pending_decision();
+            if !sub_decision && self.rollout_policy.max_plies().is_some_and(|max| plies >= max) {
+                return self.rollout_policy.evaluate(state);
+            }
+            let action = self.rollout_policy.choose_action(state, &actions, rng);
+            let recorded = self.tree_policy.uses_amaf().then(|| action.clone());
+            state.do_action(action, rng);
+            self.record(played, state, recorded);
+            if !sub_decision {
+                plies += 1;
+            }
+        }
+    }
+
+    /// Update every node from `node` up to the root with the reward for the player who moved
+    /// into that node, and the position of each node with every player's reward
+    fn backpropagate(&self, arena: &mut NodeArena<S::Action>, node: NodeId, rewards: &[f32]) {
+        let mut curr_node = Some(node);
+        while let Some(node_id) = curr_node {
+            let node = &mut arena[node_id];
+            node.update(rewards[node.player_just_moved]);
+            curr_node = node.parent;
+
+            if let Some(hash) = node.hash {
+                arena.transpositions.entry(hash)
+                    .or_insert_with(|| Transposition::new(rewards.len()))
+                    .update(rewards);
+            }
+        }
+    }
+
+    /// Add the move just played in `state` to the moves of the iteration if the tree policy needs
+    /// them. `action` is None for a chance outcome.
+    fn record(&self, played: &mut Played<S::Action>, state: &S, action: Option<S::Action>) {
+        if self.tree_policy.uses_amaf() {
+            played.push(action.map(|action| (state.get_player_just_moved(), action)));
+        }
+    }
+
+    /// Credit the children of every node from `node` up to the root whose player went on to play
+    /// their action at any later point of the iteration, all moves as first
+    fn update_amaf(&self, arena: &mut NodeArena<S::Action>, node: NodeId, played: &Played<S::Action>, rewards: &[f32]) {
+        if !self.tree_policy.uses_amaf() {
+            return;
+        }
+
+        let mut path = vec!(node);
+        while let Some(parent) = arena[*path.last().unwrap()].parent {
+            path.push(parent);
+        }
+
+        // The node at depth `d` was left by the move at `played[d]`
+        for (depth, node_id) in path.into_iter().rev().enumerate() {
+            if arena[node_id].chance {
+                continue;
+            }
+            let later = &played[depth.min(played.len())..];
+            for child in arena[node_id].children.clone() {
+                let child = &mut arena[child];
+                let played_later = later.iter().flatten()
+                    .any(|(player, action)| *player == child.player_just_moved && child.action.as_ref() == Some(action));
+                if played_later {
+                    child.amaf_visits += 1;
+                    child.amaf_wins += rewards[child.player_just_moved];
+                }
+            }
+        }
+    }
+
+    /// Keep searching on this thread until a child of `rootnode` leads both in visits and mean,
+    /// for at most `ROBUST_EXTENSION` of the budget
+    fn extend_to_robust_child(&mut self, roots: &[NodeId], rootnode: NodeId, rootstate: &S) {
+        let extension = self.budget.fraction(ROBUST_EXTENSION);
+        let begin_time = Instant::now();
+        let mut arena = mem::take(&mut self.arena);
+        let mut rng = self.rng.clone();
+        let mut extra = 0;
+        while !extension.exhausted(extra, begin_time.elapsed()) && self.robust_child(&arena, rootnode).is_none()
+            && !self.stop_flag.load(Ordering::SeqCst) {
+            extra += self.iterate(&mut arena, roots, rootstate, &mut rng, 1);
+        }
+        self.arena = arena;
+        self.rng = rng;
+        self.iterations += extra;
+    }
+
+    /// The visited child of `rootnode` with both the most visits and the highest mean, if one
+    /// child leads both
+    fn robust_child(&self, arena: &NodeArena<S::Action>, rootnode: NodeId) -> Option<NodeId> {
+        let most_visits = self.best_child_by(arena, rootnode, |stats| stats.visits as f64)?;
+        let highest_mean = self.best_child_by(arena, rootnode, |stats| stats.mean())?;
+        if arena[most_visits].visits == arena[highest_mean].visits
+            && arena[most_visits].stats().mean() == arena[highest_mean].stats().mean() {
+            Some(most_visits)
+        } else {
+            None
+        }
+    }
+
+    /// The first visited child of `rootnode` with the highest `value`
+    fn best_child_by<F: Fn(&NodeStats) -> f64>(&self, arena: &NodeArena<S::Action>, rootnode: NodeId, value: F) -> Option<NodeId> {
+        let mut best_value = f64::NEG_INFINITY;
+        let mut best_child = None;
+        for child in &arena[rootnode].children {
+            let stats = arena[*child].stats();
+            if stats.visits == 0 {
+                continue;
+            }
+            let curr_value = value(&stats);
+            if curr_value > best_value {
+                best_value = curr_value;
+                best_child = Some(*child);
+            }
+        }
+        best_child
+    }
+
+    /// Summary of the tree below `rootnode` after a search that picked `action`
+    fn result(&self, rootnode: NodeId, action: S::Action, elapsed: Duration) -> SearchResult<S::Action> {
+        let mut children: Vec<ChildStats<S::Action>> = self.arena[rootnode].children.iter()
+            .map(|child| &self.arena[*child])
+            .filter(|child| child.visits > 0)
+            .filter_map(|child| child.action.clone().map(|action| ChildStats::new(action, &child.stats())))
+            .collect();
+        children.sort_by_key(|child| ::std::cmp::Reverse(child.visits));
+
+        let mut principal_variation = vec!(action.clone());
+        let mut node = self.arena[rootnode].children.iter()
+            .find(|child| self.arena[**child].action.as_ref() == Some(&action))
+            .cloned();
+        while let Some(curr_node) = node {
+            node = self.best_child_by(&self.arena, curr_node, |stats| stats.visits as f64);
+            if let Some(action) = node.and_then(|child| self.arena[child].action.clone()) {
+                principal_variation.push(action);
+            }
+        }
+
+        SearchResult {
+            action,
+            children,
+            principal_variation,
+            iterations: self.iterations,
+            elapsed,
+        }
+    }
+
+    /// Action of the root child that `final_move` picks
+    fn best_action(&self, rootnode: NodeId) -> Option<S::Action> {
+        let arena = &self.arena;
+        let child = match self.final_move {
+            FinalMove::MostVisits => self.best_child_by(arena, rootnode, |stats| stats.visits as f64),
+            FinalMove::HighestMean => self.best_child_by(arena, rootnode, |stats| stats.mean()),
+            FinalMove::MaxRobust => self.robust_child(arena, rootnode)
+                .or_else(|| self.best_child_by(arena, rootnode, |stats| stats.visits as f64)),
+            FinalMove::LowerConfidenceBound(c) => {
+                self.best_child_by(arena, rootnode, |stats| stats.mean() - c / (stats.visits as f64).sqrt())
+            }
+        };
+        child.and_then(|child| arena[child].action.clone())
+    }
+}
+
+impl<S: Determinizable + Clone + Send> Mcts<S> {
+    /// Search the information sets of a game with hidden information instead of its states. Every
+    /// iteration plays on a random determinization of the root as the player to move sees it, so
+    /// the search cannot use anything that player does not know. Drops the current tree.
+    pub fn information_sets(&mut self, observers: Observers) {
+        self.determinizer = Some(Determinizer {
+            observers,
+            determinize: S::determinize,
+            player_to_move: S::get_player_to_move,
+            num_players: S::get_num_players,
+            observe_action: S::observe_action,
+        });
+        self.clear();
+    }
+}
+
+impl<S: StateHash + Clone + Send> Mcts<S> {
+    /// Pool the statistics of positions that different orders of moves reach, combining them
+    /// during selection as `update` describes. Has no effect on information set search. Drops the
+    /// current tree.
+    pub fn transpositions(&mut self, update: TranspositionUpdate) {
+        self.transposition = Some(TranspositionMode {
+            update,
+            hash: S::state_hash,
+        });
+        self.clear();
+    }
+
+    /// Play the move `book` holds for a position instead of searching it. Positions missing from
+    /// the book are searched as usual.
+    pub fn opening_book(&mut self, book: OpeningBook<S::Action>) {
+        self.book = Some(BookLookup {
+            book,
+            hash: S::state_hash,
+        });
+    }
+}
@@ -0,0 +1,168 @@
+use evaluator::StateFeatures;
+use rand::Rng;
+use result::SearchResult;
+use rng::GameRng;
+use search::{Mcts, SearchError};
+use serde::Serialize;
+use serde_json;
+use state::State;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Why self-play stopped before every game was recorded
+#[derive(Debug)]
+pub enum SelfPlayError {
+    /// The search could not pick a move in a position that still had actions
+    Search(SearchError),
+    /// Writing the samples failed
+    Io(io::Error),
+    /// A sample could not be serialized as JSON
+    Format(serde_json::Error),
+}
+
+impl fmt::Display for SelfPlayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SelfPlayError::Search(ref err) => write!(f, "Search failed during self-play: {}", err),
+            SelfPlayError::Io(ref err) => write!(f, "Failed to write the samples: {}", err),
+            SelfPlayError::Format(ref err) => write!(f, "Failed to serialize a sample: {}", err),
+        }
+    }
+}
+
+impl Error for SelfPlayError {}
+
+impl From<SearchError> for SelfPlayError {
+    fn from(err: SearchError) -> SelfPlayError {
+        SelfPlayError::Search(err)
+    }
+}
+
+impl From<io::Error> for SelfPlayError {
+    fn from(err: io::Error) -> SelfPlayError {
+        SelfPlayError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SelfPlayError {
+    fn from(err: serde_json::Error) -> SelfPlayError {
+        if err.is_io() {
+            SelfPlayError::Io(err.into())
+        } else {
+            SelfPlayError::Format(err)
+        }
+    }
+}
+
+/// One position of a self-play game, written as a line of JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrainingSample<A> {
+    /// Number of the game, counting from 0
+    pub game: usize,
+    /// Player who chose the move played in the position
+    pub player: usize,
+    /// Features of the position
+    pub features: Vec<f32>,
+    /// Every root child the search explored
+    pub actions: Vec<A>,
+    /// Visits of each of `actions`
+    pub visits: Vec<u32>,
+    /// Final result of the game for every player
+    pub outcome: Vec<f32>,
+}
+
+/// Plays the searcher against itself and records every position it decided as training data for
+/// an `Evaluator`
+pub struct SelfPlay<S: State> {
+    /// Number of games to play
+    pub games: usize,
+    /// Number of moves at the start of each game played in proportion to the root visits rather
+    /// than by the searcher's final move, so the games don't all follow the same line
+    pub exploration_moves: usize,
+    features: Box<StateFeatures<S>>,
+}
+
+impl<S: State + Clone + Send> SelfPlay<S> {
+    pub fn new<F: Fn(&S) -> Vec<f32> + Send + Sync + 'static>(games: usize, exploration_moves: usize, features: F) -> SelfPlay<S> {
+        SelfPlay {
+            games,
+            exploration_moves,
+            features: Box::new(features),
+        }
+    }
+
+    /// Play the games, each from a state made by `new_game`, and write one `TrainingSample` per
+    /// decided position to `out` as JSON lines. Returns the number of samples written. Positions
+    /// in the searcher's opening book are searched too, so every sample has visits to learn from.
+    ///
+    /// A failed search ends the run with its error, and the samples of the unfinished game are
+    /// not written.
+    pub fn run<W, F>(&self, mcts: &mut Mcts<S>, mut new_game: F, rng: &mut GameRng, mut out: W) -> Result<usize, SelfPlayError>
+        where W: Write, F: FnMut(&mut GameRng) -> S, S::Action: Serialize {
+        let mut written = 0;
+        for game in 0..self.games {
+            let mut state = new_game(rng);
+            mcts.clear();
+
+            let mut samples = Vec::new();
+            loop {
+                if let Some(outcome) = state.sample_chance_outcome(rng) {
+                    state.do_action(outcome.clone(), rng);
+                    mcts.advance(&outcome);
+                    continue;
+                }
+
+                if state.get_actions().is_empty() {
+                    break;
+                }
+
+                let result = mcts.analyze_without_book(&state)?;
+                let action = if samples.len() < self.exploration_moves {
+                    sample_by_visits(&result, rng)
+                } else {
+                    result.action.clone()
+                };
+
+                let features = (self.features)(&state);
+                state.do_action(action.clone(), rng);
+                mcts.advance(&action);
+                samples.push(TrainingSample {
+                    game,
+                    player: state.get_player_just_moved(),
+                    features,
+                    actions: result.children.iter().map(|child| child.action.clone()).collect(),
+                    visits: result.children.iter().map(|child| child.visits).collect(),
+                    outcome: Vec::new(),
+                });
+            }
+
+            let outcome = state.get_rewards();
+            for mut sample in samples {
+                sample.outcome = outcome.clone();
+                serde_json::to_writer(&mut out, &sample)?;
+                out.write_all(b"\n")?;
+                written += 1;
+            }
+        }
+        out.flush()?;
+        Ok(written)
+    }
+}
+
+/// A root child's action picked with probability proportional to its visits
+fn sample_by_visits<A: Clone>(result: &SearchResult<A>, rng: &mut GameRng) -> A {
+    let total: u32 = result.children.iter().map(|child| child.visits).sum();
+    if total == 0 {
+        return result.action.clone();
+    }
+
+    let mut pick = rng.gen_range(0, total);
+    for child in &result.children {
+        if pick < child.visits {
+            return child.action.clone();
+        }
+        pick -= child.visits;
+    }
+    result.action.clone()
+}
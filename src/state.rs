@@ -1,15 +1,90 @@
 use action::Action;
+use rand::Rng;
+use rng::GameRng;
 
 pub trait State {
+    /// The game's moves, which the search stores in its nodes and displays with `Debug`
+    type Action: Action;
+
     fn get_player_just_moved(&self) -> usize;
 
-    ///Actions must be translated into u32 for generic processing
-    fn get_actions(&self) -> Vec<u32>;
-    fn get_action_strings(&self) -> Vec<String>;
+    fn get_actions(&self) -> Vec<Self::Action>;
+
+    /// Perform the move according to the rules of the game. Any randomness in the rules must come
+    /// from `rng` so that games can be replayed from a seed.
+    fn do_action(&mut self, action: Self::Action, rng: &mut GameRng);
+
+    /// Result for every player from 0.0 - 1.0, indexed by player number
+    fn get_rewards(&self) -> Vec<f32>;
+
+    /// Outcomes of the random event the game is waiting on, each with its probability. Outcomes
+    /// are played with `do_action` like actions, and `get_actions` should be empty while an event
+    /// is pending. Games without random events keep the default of no outcomes.
+    fn get_chance_outcomes(&self) -> Vec<(Self::Action, f32)> {
+        Vec::new()
+    }
+
+    /// True while the player who just moved is still choosing parts of their move, such as where
+    /// to place the pieces an action gave them. `get_actions` then lists those choices and
+    /// `get_player_just_moved` stays the same until the move is complete, so the search plans
+    /// every step of the move for that player. Sub-decisions do not count as plies in a truncated
+    /// rollout.
+    fn in_pending_decision(&self) -> bool {
+        false
+    }
+
+    /// Pick one of the pending chance outcomes according to their probabilities, None if the game
+    /// is not waiting on a random event
+    fn sample_chance_outcome(&self, rng: &mut GameRng) -> Option<Self::Action> {
+        let mut outcomes = self.get_chance_outcomes();
+        let total: f32 = outcomes.iter().map(|&(_, probability)| probability).sum();
+        let mut remaining = rng.gen::<f32>() * total;
+        let last = outcomes.pop();
+        for (outcome, probability) in outcomes {
+            if remaining < probability {
+                return Some(outcome);
+            }
+            remaining -= probability;
+        }
+        last.map(|(outcome, _)| outcome)
+    }
+}
+
+/// A game with hidden information, such as a shuffled deck or the other players' hands, that
+/// `Mcts::information_sets` can search without looking at what the players cannot see
+pub trait Determinizable: State {
+    /// Number of the player whose decision the game is waiting on
+    fn get_player_to_move(&self) -> usize;
+
+    /// Number of players, each of which gets their own tree in a multi-observer search
+    fn get_num_players(&self) -> usize;
+
+    /// A copy of the game where everything `observer` cannot see is replaced by a random guess
+    /// consistent with what they have seen so far
+    fn determinize(&self, observer: usize, rng: &mut GameRng) -> Self;
+
+    /// `action` as `observer` sees it played. Moves that hide what was played, like a card laid
+    /// face down, should return the same value for every choice the observer cannot tell apart.
+    fn observe_action(&self, action: &Self::Action, _observer: usize) -> Self::Action {
+        action.clone()
+    }
+}
+
+/// A game whose positions can be told apart by a hash, so that `Mcts::transpositions` can pool
+/// the statistics of a position reached by different orders of moves
+pub trait StateHash: State {
+    /// Equal for states that play out the same from here on, including whose turn it is
+    fn state_hash(&self) -> u64;
+}
 
-    /// Perform the move according to the rules of the game
-    fn do_action(&mut self, action: u32);
+/// A game whose positions can be encoded as a fixed-length vector of numbers for a model.
+/// `features` can be passed as the state encoding `LinearEvaluator::new` and `SelfPlay::new`
+/// take, e.g. `MyState::features`.
+pub trait FeatureEncode: State {
+    /// Length of the vectors returned by `features`, the same for every state of one game
+    fn feature_len(&self) -> usize;
 
-    /// Given a player, return a result number from 0.0 - 1.0
-    fn get_result(&self, player: usize) -> f32;
+    /// Encode the position. Each index means the same thing in every state of the game, so the
+    /// game should document its layout.
+    fn features(&self) -> Vec<f32>;
 }
@@ -1,7 +1,972 @@
 extern crate boardgameai_rs;
+extern crate rand;
+extern crate serde;
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
 use boardgameai_rs::*;
+use boardgameai_rs::state::{Determinizable, FeatureEncode, State, StateHash};
+use rand::Rng;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+/// Nim where players alternate taking 1-3 chips and whoever takes the last chip wins
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Nim {
+    player_just_moved: usize,
+    chips: u32
+}
+
+impl Nim {
+    fn new(chips: u32) -> Nim {
+        Nim { player_just_moved: 1, chips }
+    }
+}
+
+impl State for Nim {
+    type Action = u32;
+
+    fn get_player_just_moved(&self) -> usize {
+        self.player_just_moved
+    }
+
+    fn get_actions(&self) -> Vec<u32> {
+        (1..4).filter(|&n| n <= self.chips).collect()
+    }
+
+    fn do_action(&mut self, action: u32, _rng: &mut GameRng) {
+        self.chips -= action;
+        self.player_just_moved = 1 - self.player_just_moved;
+    }
+
+    fn get_rewards(&self) -> Vec<f32> {
+        let mut rewards = vec!(0.0, 0.0);
+        rewards[self.player_just_moved] = 1.0;
+        rewards
+    }
+}
+
+impl StateHash for Nim {
+    fn state_hash(&self) -> u64 {
+        (self.chips as u64) << 1 | self.player_just_moved as u64
+    }
+}
+
+impl FeatureEncode for Nim {
+    fn feature_len(&self) -> usize {
+        2
+    }
+
+    fn features(&self) -> Vec<f32> {
+        vec!(self.chips as f32, self.player_just_moved as f32)
+    }
+}
 
 #[test]
 fn create_node() {
-    let node = Node::new();
+    let node: Node<u32> = Node::new();
+    assert_eq!(node.visits, 0);
+}
+
+#[test]
+fn search_finds_winning_nim_move() {
+    let mut mcts = Mcts::new(Budget::Iterations(2000));
+    // Taking 3 leaves a multiple of 4, which loses for the opponent
+    assert_eq!(mcts.search(&Nim::new(7)), Ok(3));
+    assert_eq!(mcts.iterations, 2000);
+}
+
+#[test]
+fn analyze_explains_the_chosen_move() {
+    let mut mcts = Mcts::new(Budget::Iterations(2000));
+    let result = mcts.analyze(&Nim::new(7)).unwrap();
+    assert_eq!(result.action, 3);
+    assert_eq!(result.iterations, 2000);
+
+    // Every iteration passes through one root child, listed most visited first
+    assert_eq!(result.children.len(), 3);
+    assert_eq!(result.children[0].action, 3);
+    assert!(result.children.windows(2).all(|pair| pair[0].visits >= pair[1].visits));
+    assert_eq!(result.children.iter().map(|child| child.visits).sum::<u32>(), 2000);
+    assert!(result.children.iter().all(|child| child.confidence > 0.0));
+
+    // The expected line plays out the remaining 4 chips
+    assert_eq!(result.principal_variation[0], 3);
+    assert_eq!(result.principal_variation.iter().sum::<u32>(), 7);
+}
+
+#[test]
+fn anytime_search_runs_in_steps() {
+    let state = Nim::new(7);
+    let mut mcts = Mcts::new(Budget::Iterations(0));
+    assert_eq!(mcts.current_best(), Err(SearchError::NoActions));
+
+    mcts.start(&state);
+    mcts.step(&state, 1000);
+    mcts.step(&state, 1000);
+    assert_eq!(mcts.iterations, 2000);
+    assert_eq!(mcts.current_best(), Ok(3));
+}
+
+#[test]
+fn searches_stop_early_or_on_demand() {
+    // The leader cannot be caught long before the budget is spent
+    let mut mcts = Mcts::new(Budget::Iterations(20000));
+    mcts.stop_early = true;
+    assert_eq!(mcts.search(&Pick { actions: 5, picked: None }), Ok(4));
+    assert!(mcts.iterations < 20000);
+
+    // A single action needs a single iteration
+    mcts.search(&Pick { actions: 1, picked: None }).unwrap();
+    assert_eq!(mcts.iterations, 1);
+
+    for &parallelism in &[Parallelism::Root, Parallelism::Tree] {
+        let mut mcts = Mcts::new(Budget::Iterations(20000));
+        mcts.stop_early = true;
+        mcts.threads = 2;
+        mcts.parallelism = parallelism;
+        assert_eq!(mcts.search(&Pick { actions: 5, picked: None }), Ok(4));
+        assert!(mcts.iterations < 20000);
+    }
+
+    // A stop requested before the search ends it at once, and only that search
+    let mut mcts = Mcts::new(Budget::Iterations(1000));
+    mcts.stop_handle().store(true, std::sync::atomic::Ordering::SeqCst);
+    assert_eq!(mcts.search(&Nim::new(7)), Err(SearchError::NoActions));
+    assert_eq!(mcts.search(&Nim::new(7)), Ok(3));
+    assert_eq!(mcts.iterations, 1000);
+
+    // A stopped step clears the flag, so the next step runs
+    mcts.start(&Nim::new(7));
+    mcts.stop_handle().store(true, std::sync::atomic::Ordering::SeqCst);
+    mcts.step(&Nim::new(7), 100);
+    assert_eq!(mcts.iterations, 0);
+    mcts.step(&Nim::new(7), 100);
+    assert_eq!(mcts.iterations, 100);
+}
+
+#[test]
+fn pondering_searches_until_stopped() {
+    let mut mcts = Mcts::new(Budget::Iterations(100));
+    mcts.threads = 2;
+    let ponder = mcts.ponder(Nim::new(7));
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    let mut mcts = ponder.stop();
+    assert!(mcts.iterations > 100);
+    assert_eq!(mcts.budget, Budget::Iterations(100));
+    assert_eq!(mcts.parallelism, Parallelism::Root);
+
+    // The opponent's move keeps the subtree grown below it
+    mcts.advance(&3);
+    let root = mcts.root.expect("Pondered move was dropped");
+    assert!(!mcts.arena[root].children.is_empty());
+}
+
+#[test]
+fn search_without_actions_is_an_error() {
+    let mut mcts = Mcts::new(Budget::Iterations(10));
+    assert_eq!(mcts.search(&Nim::new(0)), Err(SearchError::NoActions));
+
+    // Nothing is explored without any iterations either
+    let mut mcts = Mcts::new(Budget::Iterations(0));
+    assert_eq!(mcts.search(&Nim::new(5)), Err(SearchError::NoActions));
+}
+
+/// Knows that the player to move in Nim loses on a multiple of 4 chips, and keeps the size of
+/// every batch it is given
+struct NimOracle {
+    batches: Arc<Mutex<Vec<usize>>>,
+}
+
+impl Evaluator<Nim> for NimOracle {
+    fn evaluate(&self, states: &[Nim]) -> Vec<Evaluation> {
+        self.batches.lock().unwrap().push(states.len());
+        states.iter().map(|state| {
+            let actions = state.get_actions().len();
+            let to_move = 1 - state.player_just_moved;
+            let winner = if state.chips % 4 == 0 { state.player_just_moved } else { to_move };
+            let mut values = vec!(0.0, 0.0);
+            values[winner] = 1.0;
+            Evaluation { priors: vec!(1.0 / actions as f32; actions), values }
+        }).collect()
+    }
+}
+
+#[test]
+fn evaluator_replaces_rollouts_in_batches() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let mut mcts = Mcts::new(Budget::Iterations(50));
+    mcts.tree_policy = Box::new(Puct::default());
+    mcts.evaluator = Some(Box::new(NimOracle { batches: batches.clone() }));
+    mcts.batch_size = 4;
+    assert_eq!(mcts.search(&Nim::new(7)), Ok(3));
+    assert_eq!(mcts.iterations, 50);
+
+    // The root is evaluated on its own, then the leaves up to 4 at a time, leaving out the
+    // finished games
+    {
+        let batches = batches.lock().unwrap();
+        assert_eq!(batches[0], 1);
+        assert!(batches[1..].contains(&4));
+        assert!(batches[1..].iter().all(|&batch| batch <= 4));
+        assert!(batches[1..].iter().sum::<usize>() <= 50);
+    }
+
+    mcts.threads = 2;
+    mcts.parallelism = Parallelism::Tree;
+    assert_eq!(mcts.search(&Nim::new(11)), Ok(3));
+}
+
+#[test]
+fn linear_evaluator_priors_order_the_expansions() {
+    let mut evaluator = LinearEvaluator::new(2, |state: &Nim| vec!(state.chips as f32), 1, |_: &Nim, action: &u32| vec!(*action as f32), 1);
+    let evaluation = evaluator.evaluate(&[Nim::new(7)]).remove(0);
+    assert_eq!(evaluation.values, vec!(0.5, 0.5));
+    assert!(evaluation.priors.iter().all(|&prior| (prior - 1.0 / 3.0).abs() < 1e-6));
+
+    // Weighing the chips taken makes taking 3 the first child expanded
+    evaluator.policy_weights = vec!(1.0);
+    let evaluation = evaluator.evaluate(&[Nim::new(7)]).remove(0);
+    assert!(evaluation.priors[2] > evaluation.priors[1] && evaluation.priors[1] > evaluation.priors[0]);
+
+    let mut mcts = Mcts::new(Budget::Iterations(1));
+    mcts.evaluator = Some(Box::new(evaluator));
+    mcts.search(&Nim::new(7)).unwrap();
+    let root = mcts.root.unwrap();
+    let first = mcts.arena[root].children[0];
+    assert_eq!(mcts.arena[first].action, Some(3));
+    assert!((mcts.arena[first].prior - evaluation.priors[2]).abs() < 1e-6);
+}
+
+#[test]
+fn self_play_writes_a_sample_per_move() {
+    let mut mcts = Mcts::new(Budget::Iterations(200));
+    mcts.rng = seeded_rng(1);
+    // Book moves are searched anyway, for visits to learn from
+    let mut book = OpeningBook::new();
+    book.fill(&mut Mcts::new(Budget::Iterations(200)), &Nim::new(9), 1, 1, &mut seeded_rng(0));
+    mcts.opening_book(book);
+    let self_play = SelfPlay::new(3, 2, |state: &Nim| vec!(state.chips as f32));
+    let mut out = Vec::new();
+    let written = self_play.run(&mut mcts, |_| Nim::new(9), &mut seeded_rng(2), &mut out).unwrap();
+
+    let samples: Vec<TrainingSample<u32>> = String::from_utf8(out).unwrap().lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(samples.len(), written);
+    for game in 0..3 {
+        let moves: Vec<&TrainingSample<u32>> = samples.iter().filter(|sample| sample.game == game).collect();
+        assert_eq!(moves[0].features, vec!(9.0));
+        assert_eq!(moves[0].actions.len(), 3);
+        assert!(moves[0].visits.iter().sum::<u32>() >= 200);
+        // Whoever took the last chip won
+        let last = moves.last().unwrap();
+        assert_eq!(last.outcome[last.player], 1.0);
+    }
+
+    // A search that cannot pick a move ends the run instead of recording an unfinished game
+    let mut out = Vec::new();
+    match self_play.run(&mut Mcts::new(Budget::Iterations(0)), |_| Nim::new(9), &mut seeded_rng(2), &mut out) {
+        Err(SelfPlayError::Search(SearchError::NoActions)) => {}
+        other => panic!("Expected a search error, got {:?}", other),
+    }
+    assert!(out.is_empty());
+
+    // A sample that cannot be serialized is told apart from a failed write
+    let bell_play = SelfPlay::new(1, 0, |_: &Belfry| Vec::new());
+    match bell_play.run(&mut Mcts::new(Budget::Iterations(10)), |_| Belfry { rung: false }, &mut seeded_rng(2), Vec::new()) {
+        Err(SelfPlayError::Format(_)) => {}
+        other => panic!("Expected a format error, got {:?}", other),
+    }
+    match self_play.run(&mut mcts, |_| Nim::new(9), &mut seeded_rng(2), FullDisk) {
+        Err(SelfPlayError::Io(_)) => {}
+        other => panic!("Expected an I/O error, got {:?}", other),
+    }
+}
+
+/// A writer that fails every write, as a full disk would
+struct FullDisk;
+
+impl Write for FullDisk {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::Other, "No space left on device"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn encoded_features_feed_models_and_training_data() {
+    let state = Nim::new(5);
+    assert_eq!(state.features(), vec!(5.0, 1.0));
+
+    let mut model = LinearEvaluator::new(2, Nim::features, state.feature_len(), |_: &Nim, action: &u32| vec!(*action as f32), 1);
+    model.value_weights[0] = vec!(1.0, 0.0);
+    let evaluation = model.evaluate(::std::slice::from_ref(&state)).remove(0);
+    assert!(evaluation.values[0] > 0.99 && evaluation.values[1] == 0.5);
+
+    let mut out = Vec::new();
+    let mut mcts = Mcts::new(Budget::Iterations(100));
+    SelfPlay::new(1, 0, Nim::features).run(&mut mcts, |_| Nim::new(5), &mut seeded_rng(0), &mut out).unwrap();
+    let first: TrainingSample<u32> = serde_json::from_str(String::from_utf8(out).unwrap().lines().next().unwrap()).unwrap();
+    assert_eq!(first.features.len(), state.feature_len());
+}
+
+#[test]
+fn every_tree_policy_finds_winning_nim_move() {
+    let policies: Vec<Box<dyn TreePolicy>> = vec!(
+        Box::new(Ucb1::new(1.0)),
+        Box::new(Ucb1Tuned::new()),
+        Box::new(Puct::default()),
+        Box::new(Rave::default())
+    );
+
+    for policy in policies {
+        let mut mcts = Mcts::new(Budget::Iterations(2000));
+        mcts.tree_policy = policy;
+        assert_eq!(mcts.search(&Nim::new(6)), Ok(2));
+    }
+}
+
+#[test]
+fn rave_credits_actions_played_later_in_the_iteration() {
+    let mut mcts = Mcts::new(Budget::Iterations(500));
+    mcts.tree_policy = Box::new(Rave::new(0.4, RaveSchedule::MinimumError(0.1)));
+    assert_eq!(mcts.search(&Nim::new(9)), Ok(1));
+
+    // Every visit of a root child played its action first, and other iterations played it later
+    let root = mcts.root.unwrap();
+    let children = &mcts.arena[root].children;
+    assert!(children.iter().all(|child| mcts.arena[*child].amaf_visits >= mcts.arena[*child].visits));
+    assert!(children.iter().any(|child| mcts.arena[*child].amaf_visits > mcts.arena[*child].visits));
+
+    // Without RAVE nothing is recorded
+    let mut mcts = Mcts::new(Budget::Iterations(100));
+    mcts.search(&Nim::new(9)).unwrap();
+    let root = mcts.root.unwrap();
+    assert!(mcts.arena[root].children.iter().all(|child| mcts.arena[*child].amaf_visits == 0));
+}
+
+#[test]
+fn greedy_and_truncated_rollouts_find_winning_nim_move() {
+    // Greedy playouts always leave the opponent a multiple of 4 when they can
+    let mut mcts = Mcts::new(Budget::Iterations(500));
+    mcts.rollout_policy = Box::new(EpsilonGreedyRollout::new(0.1, |state: &Nim, action| {
+        if (state.chips - *action).is_multiple_of(4) { 1.0 } else { 0.0 }
+    }));
+    assert_eq!(mcts.search(&Nim::new(9)), Ok(1));
+
+    // A perfect evaluator after a single ply solves the position from the first expansions
+    let mut mcts = Mcts::new(Budget::Iterations(500));
+    mcts.rollout_policy = Box::new(TruncatedRollout::new(UniformRollout::new(), 1, |state: &Nim| {
+        let mut rewards = vec!(0.0, 0.0);
+        let winner = if state.chips.is_multiple_of(4) { state.player_just_moved } else { 1 - state.player_just_moved };
+        rewards[winner] = 1.0;
+        rewards
+    }));
+    assert_eq!(mcts.search(&Nim::new(9)), Ok(1));
+}
+
+#[test]
+fn parallel_searches_find_winning_nim_move() {
+    for &parallelism in &[Parallelism::Root, Parallelism::Tree] {
+        let mut mcts = Mcts::new(Budget::Iterations(4000));
+        mcts.threads = 4;
+        mcts.parallelism = parallelism;
+        assert_eq!(mcts.search(&Nim::new(10)), Ok(2));
+        assert_eq!(mcts.iterations, 4000);
+
+        // Every completed iteration is counted at the root, with no virtual loss left behind
+        let root = mcts.root.unwrap();
+        assert_eq!(mcts.arena[root].visits, 4000);
+    }
+}
+
+#[test]
+fn advance_reuses_subtree() {
+    let mut state = Nim::new(10);
+    let mut rng = seeded_rng(0);
+    let mut mcts = Mcts::new(Budget::Iterations(1000));
+    let action = mcts.search(&state).unwrap();
+    let total_nodes = mcts.arena.len();
+
+    // Keep the grandchild reached by our move and the opponent's reply
+    state.do_action(action, &mut rng);
+    mcts.advance(&action);
+    state.do_action(1, &mut rng);
+    mcts.advance(&1);
+
+    let root = mcts.root.expect("Explored grandchild was dropped");
+    let kept_visits = mcts.arena[root].visits;
+    assert!(kept_visits > 0);
+    assert!(mcts.arena.len() < total_nodes);
+    assert_eq!(mcts.arena[root].parent, None);
+
+    mcts.search(&state).unwrap();
+    assert_eq!(mcts.root, Some(root));
+    assert_eq!(mcts.arena[root].visits, kept_visits + 1000);
+
+    // An unexplored action drops the tree
+    mcts.advance(&42);
+    assert!(mcts.root.is_none());
+}
+
+#[test]
+fn unrelated_positions_search_fresh_trees() {
+    let mut mcts = Mcts::new(Budget::Iterations(1000));
+    assert_eq!(mcts.search(&Nim::new(7)), Ok(3));
+
+    // Nim 10 has the same mover and actions as Nim 7, but none of its statistics
+    assert_eq!(mcts.search(&Nim::new(10)), Ok(2));
+    let root = mcts.root.unwrap();
+    assert_eq!(mcts.arena[root].visits, 1000);
+}
+
+#[test]
+fn saved_trees_load_and_resume() {
+    let state = Nim::new(10);
+    let mut mcts = Mcts::new(Budget::Iterations(1000));
+    mcts.search(&state).unwrap();
+    let mut file = Vec::new();
+    mcts.save_tree(&state, &mut file).unwrap();
+
+    let mut loaded: Mcts<Nim> = Mcts::new(Budget::Iterations(500));
+    let loaded_state = loaded.load_tree(&file[..]).unwrap();
+    assert_eq!(loaded_state.chips, 10);
+    assert_eq!(loaded.arena.len(), mcts.arena.len());
+
+    // The loaded tree is searched further instead of being replaced
+    let root = loaded.root.unwrap();
+    loaded.search(&loaded_state).unwrap();
+    assert_eq!(loaded.arena[root].visits, 1500);
+
+    let file = String::from_utf8(file).unwrap();
+    let other_version = file.replacen("\"version\":1", "\"version\":2", 1);
+    match loaded.load_tree(other_version.as_bytes()) {
+        Err(SaveError::Version(2)) => {}
+        other => panic!("Expected a version error, got {:?}", other.map(|_| ())),
+    }
+
+    // Ids pointing past the end of the arena are refused rather than loaded
+    for field in &["\"root\":{\"index\":", "\"children\":[{\"index\":"] {
+        let out_of_range = file.replacen(*field, &format!("{}99999", field), 1);
+        match loaded.load_tree(out_of_range.as_bytes()) {
+            Err(SaveError::Format(_)) => {}
+            other => panic!("Expected a format error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    // So are nodes that don't form one tree below the root, which would hang backpropagation
+    // or panic when rerooting
+    let tree: serde_json::Value = serde_json::from_str(&file).unwrap();
+    let index = |id: &serde_json::Value| id["index"].as_u64().unwrap() as usize;
+    let root = index(&tree["root"]);
+    let child = index(&tree["arena"]["nodes"][root]["children"][0]);
+    let grandchild = index(&tree["arena"]["nodes"][child]["children"][0]);
+    let corruptions: [&dyn Fn(&mut serde_json::Value); 3] = [
+        // The child and grandchild are each other's parent, cut off from the root
+        &|nodes| {
+            nodes[root]["children"].as_array_mut().unwrap().remove(0);
+            let child_id = nodes[child]["id"].clone();
+            nodes[grandchild]["children"].as_array_mut().unwrap().push(child_id);
+            nodes[child]["parent"] = nodes[grandchild]["id"].clone();
+        },
+        // The grandchild names the root as its parent
+        &|nodes| nodes[grandchild]["parent"] = nodes[root]["id"].clone(),
+        // The root lists the grandchild as well as the child does
+        &|nodes| {
+            let grandchild_id = nodes[grandchild]["id"].clone();
+            nodes[root]["children"].as_array_mut().unwrap().push(grandchild_id);
+        },
+    ];
+    for corrupt in &corruptions {
+        let mut corrupted = tree.clone();
+        corrupt(&mut corrupted["arena"]["nodes"]);
+        match loaded.load_tree(corrupted.to_string().as_bytes()) {
+            Err(SaveError::Format(_)) => {}
+            other => panic!("Expected a format error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    // The tree itself is still accepted
+    assert!(loaded.load_tree(tree.to_string().as_bytes()).is_ok());
+}
+
+#[test]
+fn opening_book_plays_known_positions_and_searches_the_rest() {
+    let mut book = OpeningBook::new();
+    let mut mcts = Mcts::new(Budget::Iterations(2000));
+    // The position and the three replies to it
+    book.fill(&mut mcts, &Nim::new(7), 2, 3, &mut seeded_rng(0));
+    assert_eq!(book.len(), 4);
+    assert_eq!(book.get(&Nim::new(7)).map(|entry| entry.action), Some(3));
+
+    let mut file = Vec::new();
+    book.save(&mut file).unwrap();
+    let mut player = Mcts::new(Budget::Iterations(2000));
+    player.opening_book(OpeningBook::load(&file[..]).unwrap());
+
+    let result = player.analyze(&Nim::new(7)).unwrap();
+    assert_eq!((result.action, result.iterations), (3, 0));
+    let result = player.analyze(&Nim::new(6)).unwrap();
+    assert_eq!((result.action, result.iterations), (2, 2000));
+}
+
+#[test]
+fn tree_stats_describe_the_search_and_the_node_cap_holds() {
+    let mut mcts = Mcts::new(Budget::Iterations(2000));
+    let action = mcts.search(&Nim::new(7)).unwrap();
+    let stats = mcts.arena.tree_stats(mcts.root.unwrap());
+    assert_eq!(stats.nodes, mcts.arena.len());
+    assert!(stats.max_depth <= 7);
+    assert!(stats.average_depth > 1.0 && stats.average_depth < stats.max_depth as f64);
+    assert_eq!(stats.branching_factors[0], 3.0);
+    assert_eq!(stats.most_visited_path[0], action);
+    assert!(stats.bytes >= stats.nodes * std::mem::size_of::<Node<u32>>());
+
+    let mut mcts = Mcts::new(Budget::Iterations(2000));
+    mcts.max_nodes = Some(50);
+    assert!(mcts.search(&Nim::new(15)).is_ok());
+    assert_eq!(mcts.iterations, 2000);
+    assert_eq!(mcts.arena.len(), 50);
+}
+
+#[test]
+fn export_writes_the_pruned_tree() {
+    let mut mcts = Mcts::new(Budget::Iterations(500));
+    mcts.search(&Nim::new(7)).unwrap();
+    let root = mcts.root.unwrap();
+    let filter = ExportFilter::new(20, Some(2));
+
+    // The root, plus the children and grandchildren with at least 20 visits
+    let kept = 1 + mcts.arena[root].children.iter()
+        .filter(|child| mcts.arena[**child].visits >= 20)
+        .map(|child| 1 + mcts.arena[*child].children.iter().filter(|grandchild| mcts.arena[**grandchild].visits >= 20).count())
+        .sum::<usize>();
+
+    let mut dot = Vec::new();
+    mcts.arena.write_dot(root, filter, &mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph tree {"));
+    assert_eq!(dot.matches("[label=").count(), kept);
+    assert_eq!(dot.matches(" -> ").count(), kept - 1);
+
+    let mut json = Vec::new();
+    mcts.arena.write_json(root, filter, &mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    assert!(json.starts_with("{\"action\":null,\"wins\":"));
+    assert_eq!(json.matches("\"visits\":").count(), kept);
+    assert!(json.contains("{\"action\":\"3\""));
+
+    // A result that is not a number is written as null, keeping the JSON valid
+    mcts.arena[root].wins = f32::NAN;
+    let mut json = Vec::new();
+    mcts.arena.write_json(root, filter, &mut json).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert!(value["wins"].is_null());
+}
+
+/// An action whose `Debug` output has a control character, quotes and a backslash in it
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Bell;
+
+impl fmt::Debug for Bell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Ring\x07 \"bell\"\\")
+    }
+}
+
+/// Bells cannot be written down, so any sample with one fails to serialize
+impl serde::Serialize for Bell {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("a bell cannot be written down"))
+    }
+}
+
+/// A single move that rings the bell
+#[derive(Clone)]
+struct Belfry {
+    rung: bool,
+}
+
+impl State for Belfry {
+    type Action = Bell;
+
+    fn get_player_just_moved(&self) -> usize {
+        0
+    }
+
+    fn get_actions(&self) -> Vec<Bell> {
+        if self.rung { Vec::new() } else { vec!(Bell) }
+    }
+
+    fn do_action(&mut self, _action: Bell, _rng: &mut GameRng) {
+        self.rung = true;
+    }
+
+    fn get_rewards(&self) -> Vec<f32> {
+        vec!(1.0)
+    }
+}
+
+#[test]
+fn dot_labels_replace_control_characters() {
+    let mut mcts = Mcts::new(Budget::Iterations(10));
+    mcts.search(&Belfry { rung: false }).unwrap();
+
+    let mut dot = Vec::new();
+    mcts.arena.write_dot(mcts.root.unwrap(), ExportFilter::default(), &mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.contains("[label=\"Ring  \\\"bell\\\"\\\\\\nW/V: "));
+    assert!(!dot.contains('\x07'));
+}
+
+#[test]
+fn same_seed_reproduces_search() {
+    let child_visits = |seed| {
+        let mut mcts = Mcts::new(Budget::Iterations(500));
+        mcts.rng = seeded_rng(seed);
+        mcts.search(&Nim::new(15)).unwrap();
+        let root = mcts.root.unwrap();
+        mcts.arena[root].children.iter()
+            .map(|child| (mcts.arena[*child].action, mcts.arena[*child].visits))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(child_visits(7), child_visits(7));
+    assert_ne!(child_visits(7), child_visits(8));
+}
+
+/// One player chooses between a sure 0.6 and a coin flip that only pays out 30% of the time
+#[derive(Clone)]
+struct Gamble {
+    flipping: bool,
+    reward: Option<f32>,
+}
+
+impl State for Gamble {
+    type Action = u32;
+
+    fn get_player_just_moved(&self) -> usize {
+        0
+    }
+
+    fn get_actions(&self) -> Vec<u32> {
+        if self.flipping || self.reward.is_some() { Vec::new() } else { vec!(0, 1) }
+    }
+
+    fn do_action(&mut self, action: u32, _rng: &mut GameRng) {
+        if self.flipping {
+            self.flipping = false;
+            self.reward = Some(action as f32);
+        } else if action == 0 {
+            self.reward = Some(0.6);
+        } else {
+            self.flipping = true;
+        }
+    }
+
+    fn get_rewards(&self) -> Vec<f32> {
+        vec!(self.reward.unwrap_or(0.0))
+    }
+
+    fn get_chance_outcomes(&self) -> Vec<(u32, f32)> {
+        if self.flipping { vec!((0, 0.7), (1, 0.3)) } else { Vec::new() }
+    }
+}
+
+#[test]
+fn chance_outcomes_are_sampled_into_their_own_children() {
+    let mut mcts = Mcts::new(Budget::Iterations(2000));
+    mcts.rng = seeded_rng(1);
+    assert_eq!(mcts.search(&Gamble { flipping: false, reward: None }), Ok(0));
+
+    let root = mcts.root.unwrap();
+    let gamble = mcts.arena[root].children.iter()
+        .map(|child| &mcts.arena[*child])
+        .find(|node| node.action == Some(1))
+        .unwrap();
+    assert!(gamble.chance);
+
+    let outcome_visits = |outcome| gamble.children.iter()
+        .map(|child| &mcts.arena[*child])
+        .find(|node| node.action == Some(outcome))
+        .unwrap()
+        .visits;
+    assert_eq!(gamble.children.len(), 2);
+    assert!(outcome_visits(0) > outcome_visits(1));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Guess {
+    Pass,
+    Heads,
+    Tails,
+}
+
+/// A coin is flipped out of sight, then the player either guesses the side for 1.0 or passes for
+/// a sure 0.6
+#[derive(Clone)]
+struct SecretCoin {
+    heads: bool,
+    reward: Option<f32>,
+}
+
+impl State for SecretCoin {
+    type Action = Guess;
+
+    fn get_player_just_moved(&self) -> usize {
+        0
+    }
+
+    fn get_actions(&self) -> Vec<Guess> {
+        if self.reward.is_some() { Vec::new() } else { vec!(Guess::Pass, Guess::Heads, Guess::Tails) }
+    }
+
+    fn do_action(&mut self, action: Guess, _rng: &mut GameRng) {
+        self.reward = Some(match action {
+            Guess::Pass => 0.6,
+            Guess::Heads if self.heads => 1.0,
+            Guess::Tails if !self.heads => 1.0,
+            _ => 0.0,
+        });
+    }
+
+    fn get_rewards(&self) -> Vec<f32> {
+        vec!(self.reward.unwrap_or(0.0))
+    }
+}
+
+impl Determinizable for SecretCoin {
+    fn get_player_to_move(&self) -> usize {
+        0
+    }
+
+    fn get_num_players(&self) -> usize {
+        1
+    }
+
+    fn determinize(&self, _observer: usize, rng: &mut GameRng) -> SecretCoin {
+        SecretCoin { heads: rng.gen(), reward: self.reward }
+    }
+}
+
+#[test]
+fn information_set_search_does_not_peek_at_hidden_state() {
+    let state = SecretCoin { heads: true, reward: None };
+
+    // Searching the true state sees the coin
+    let mut mcts = Mcts::new(Budget::Iterations(2000));
+    mcts.rng = seeded_rng(3);
+    assert_eq!(mcts.search(&state), Ok(Guess::Heads));
+
+    for observers in [Observers::Single, Observers::Multi] {
+        let mut mcts = Mcts::new(Budget::Iterations(2000));
+        mcts.rng = seeded_rng(3);
+        mcts.information_sets(observers);
+        assert_eq!(mcts.search(&state), Ok(Guess::Pass));
+    }
+}
+
+/// The player picks one of two bags and then, as part of the same move, a coin from it
+#[derive(Clone)]
+struct Bags {
+    bag: Option<usize>,
+    coin: Option<usize>,
+}
+
+impl Bags {
+    fn reward(&self) -> Option<f32> {
+        let values = [[0.5, 0.5], [0.1, 0.9]];
+        match (self.bag, self.coin) {
+            (Some(bag), Some(coin)) => Some(values[bag][coin]),
+            _ => None
+        }
+    }
+}
+
+impl State for Bags {
+    type Action = usize;
+
+    fn get_player_just_moved(&self) -> usize {
+        0
+    }
+
+    fn get_actions(&self) -> Vec<usize> {
+        if self.coin.is_some() { Vec::new() } else { vec!(0, 1) }
+    }
+
+    fn do_action(&mut self, action: usize, _rng: &mut GameRng) {
+        if self.bag.is_none() {
+            self.bag = Some(action);
+        } else {
+            self.coin = Some(action);
+        }
+    }
+
+    fn get_rewards(&self) -> Vec<f32> {
+        vec!(self.reward().unwrap_or(0.0))
+    }
+
+    fn in_pending_decision(&self) -> bool {
+        self.bag.is_some() && self.coin.is_none()
+    }
+}
+
+#[test]
+fn sub_decisions_are_planned_within_the_move() {
+    // Truncating before any ply would evaluate a half-finished move if the coin counted as one
+    let mut mcts = Mcts::new(Budget::Iterations(1000));
+    mcts.rollout_policy = Box::new(TruncatedRollout::new(UniformRollout::new(), 0, |state: &Bags| {
+        vec!(state.reward().expect("Evaluated in the middle of a move"))
+    }));
+    assert_eq!(mcts.search(&Bags { bag: None, coin: None }), Ok(1));
+
+    let root = mcts.root.unwrap();
+    let bag = mcts.arena[root].children.iter()
+        .map(|child| &mcts.arena[*child])
+        .find(|node| node.action == Some(1))
+        .unwrap();
+    let best_coin = bag.children.iter()
+        .map(|child| &mcts.arena[*child])
+        .max_by_key(|node| node.visits)
+        .unwrap();
+    assert_eq!(best_coin.action, Some(1));
+}
+
+/// One pick out of many actions, each paying its number divided by the number of actions
+#[derive(Clone)]
+struct Pick {
+    actions: u32,
+    picked: Option<u32>,
+}
+
+impl State for Pick {
+    type Action = u32;
+
+    fn get_player_just_moved(&self) -> usize {
+        0
+    }
+
+    fn get_actions(&self) -> Vec<u32> {
+        if self.picked.is_some() { Vec::new() } else { (0..self.actions).collect() }
+    }
+
+    fn do_action(&mut self, action: u32, _rng: &mut GameRng) {
+        self.picked = Some(action);
+    }
+
+    fn get_rewards(&self) -> Vec<f32> {
+        vec!(self.picked.map_or(0.0, |picked| picked as f32 / self.actions as f32))
+    }
+}
+
+#[test]
+fn every_final_move_picks_the_best_pick() {
+    let final_moves = [FinalMove::MostVisits, FinalMove::HighestMean, FinalMove::MaxRobust, FinalMove::LowerConfidenceBound(1.0)];
+    for &final_move in &final_moves {
+        let mut mcts = Mcts::new(Budget::Iterations(300));
+        mcts.final_move = final_move;
+        assert_eq!(mcts.search(&Pick { actions: 5, picked: None }), Ok(4));
+        assert!(mcts.iterations >= 300);
+    }
+}
+
+/// Always explores the child with the worst mean, so the most visited child never has the best
+struct Pessimist;
+
+impl TreePolicy for Pessimist {
+    fn score(&self, _parent: &NodeStats, child: &NodeStats) -> f64 {
+        -child.mean()
+    }
+}
+
+#[test]
+fn max_robust_extends_by_a_share_of_the_budget() {
+    let mut mcts = Mcts::new(Budget::Iterations(300));
+    mcts.final_move = FinalMove::MaxRobust;
+    mcts.tree_policy = Box::new(Pessimist);
+    assert_eq!(mcts.search(&Pick { actions: 5, picked: None }), Ok(0));
+    assert_eq!(mcts.iterations, 450);
+
+    let budget = std::time::Duration::from_millis(200);
+    mcts.budget = Budget::Time(budget);
+    let result = mcts.analyze(&Pick { actions: 5, picked: None }).unwrap();
+    assert!(result.elapsed >= budget.mul_f64(1.0 + ROBUST_EXTENSION));
+    assert!(result.elapsed < budget * 2);
+}
+
+#[test]
+fn progressive_widening_expands_by_prior() {
+    let mut mcts = Mcts::new(Budget::Iterations(100));
+    mcts.widening = Some(ProgressiveWidening::new(1.0, 0.5));
+    mcts.action_prior = Some(Box::new(|_: &Pick, action: &u32| *action as f32));
+    assert_eq!(mcts.search(&Pick { actions: 50, picked: None }), Ok(49));
+
+    // Only sqrt(100) of the 50 actions were expanded, the highest weighted first
+    let root = mcts.root.unwrap();
+    let children = &mcts.arena[root].children;
+    assert!(children.len() <= 10);
+    assert_eq!(mcts.arena[children[0]].action, Some(49));
+    let total: f32 = (0..50).map(|action| action as f32).sum();
+    assert_eq!(mcts.arena[children[0]].prior, 49.0 / total);
+}
+
+/// Two picks of item 0 or item 1, paying by how many of each were picked in whichever order
+#[derive(Clone)]
+struct TwoPicks {
+    picked: [u32; 2],
+}
+
+impl State for TwoPicks {
+    type Action = usize;
+
+    fn get_player_just_moved(&self) -> usize {
+        0
+    }
+
+    fn get_actions(&self) -> Vec<usize> {
+        if self.picked[0] + self.picked[1] == 2 { Vec::new() } else { vec!(0, 1) }
+    }
+
+    fn do_action(&mut self, action: usize, _rng: &mut GameRng) {
+        self.picked[action] += 1;
+    }
+
+    fn get_rewards(&self) -> Vec<f32> {
+        vec!(match self.picked {
+            [2, 0] => 0.9,
+            [1, 1] => 0.5,
+            _ => 0.0,
+        })
+    }
+}
+
+impl StateHash for TwoPicks {
+    fn state_hash(&self) -> u64 {
+        (self.picked[0] * 3 + self.picked[1]) as u64
+    }
+}
+
+#[test]
+fn transpositions_pool_visits_of_equal_positions() {
+    for &update in &[TranspositionUpdate::Uct1, TranspositionUpdate::Uct2, TranspositionUpdate::Uct3] {
+        let mut mcts = Mcts::new(Budget::Iterations(500));
+        mcts.transpositions(update);
+        assert_eq!(mcts.search(&TwoPicks { picked: [0, 0] }), Ok(0));
+
+        // Both orders of picking one of each reach the same position
+        let root = mcts.root.unwrap();
+        let one_of_each: u32 = mcts.arena[root].children.iter()
+            .flat_map(|child| mcts.arena[*child].children.iter())
+            .map(|grandchild| &mcts.arena[*grandchild])
+            .filter(|node| node.hash == Some(4))
+            .map(|node| node.visits)
+            .sum();
+        assert_eq!(mcts.arena.transpositions[&4].visits, one_of_each);
+        assert_eq!(mcts.arena.transpositions[&0].visits, 500);
+    }
 }